@@ -0,0 +1,279 @@
+//! User-defined command aliases and external subcommands.
+//!
+//! An `[aliases]` table in a config file maps a name to one or more commands
+//! separated by `;`, with `$1`..`$9` (and `$@` for every argument) replaced
+//! by the arguments the alias is called with:
+//!
+//! ```toml
+//! [aliases]
+//! snap = "snapshot -i -c"
+//! login = "find label Email fill $1; find label Password fill $2; find role button click --name 'Sign in'"
+//! ```
+//!
+//! A command that is neither built in nor an alias runs `agent-browser-<name>`
+//! from `PATH`, git-style, with the session exported to it.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::{exit, Command};
+
+use crate::color;
+use crate::flags::{split_words, Flags};
+
+/// Commands an alias stands for, with its parameters substituted. An alias
+/// that uses no parameters takes any arguments on its last command, so
+/// `snap -d 3` runs `snapshot -i -c -d 3`.
+pub fn expand(name: &str, template: &str, params: &[String]) -> Result<Vec<Vec<String>>, String> {
+    let mut commands = Vec::new();
+    let mut used_params = false;
+    for part in split_commands(template) {
+        let words = split_words(&part).map_err(|e| format!("{} in alias '{}'", e, name))?;
+        if words.is_empty() {
+            continue;
+        }
+        let mut command = Vec::new();
+        for word in words {
+            if word == "$@" {
+                used_params = true;
+                command.extend(params.iter().cloned());
+                continue;
+            }
+            let (word, used) = substitute(&word, params).ok_or_else(|| {
+                format!(
+                    "Alias '{}' needs more arguments than the {} given: {}",
+                    name,
+                    params.len(),
+                    template
+                )
+            })?;
+            used_params |= used;
+            command.push(word);
+        }
+        commands.push(command);
+    }
+    if commands.is_empty() {
+        return Err(format!("Alias '{}' is empty", name));
+    }
+    if !used_params {
+        if let Some(last) = commands.last_mut() {
+            last.extend(params.iter().cloned());
+        }
+    }
+    Ok(commands)
+}
+
+/// Split a template on `;` outside quotes
+fn split_commands(template: &str) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut quote = None;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        let part = parts.last_mut().expect("never empty");
+        match (c, quote) {
+            (';', None) => {
+                parts.push(String::new());
+                continue;
+            }
+            ('\\', q) if q != Some('\'') => {
+                part.push(c);
+                if let Some(next) = chars.next() {
+                    part.push(next);
+                }
+                continue;
+            }
+            ('\'' | '"', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            _ => {}
+        }
+        part.push(c);
+    }
+    parts
+}
+
+/// Replace `$1`..`$9` in `word`; `None` if one of them wasn't given
+fn substitute(word: &str, params: &[String]) -> Option<(String, bool)> {
+    let mut out = String::new();
+    let mut used = false;
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek().and_then(|d| d.to_digit(10)) {
+            Some(n) if c == '$' && n > 0 => {
+                chars.next();
+                out.push_str(params.get(n as usize - 1)?);
+                used = true;
+            }
+            _ => out.push(c),
+        }
+    }
+    Some((out, used))
+}
+
+/// Aliases being run, outermost first, passed down to the invocations an
+/// alias runs so one that calls itself is caught instead of looping forever
+const CHAIN_VAR: &str = "AGENT_BROWSER_ALIAS_CHAIN";
+
+/// How deep aliases may call other aliases
+const MAX_DEPTH: usize = 16;
+
+/// The alias chain to pass on when running alias `name`, or why it can't run
+pub fn enter(name: &str) -> Result<String, String> {
+    push_chain(&env::var(CHAIN_VAR).unwrap_or_default(), name)
+}
+
+fn push_chain(chain: &str, name: &str) -> Result<String, String> {
+    let mut names: Vec<&str> = chain.split(',').filter(|n| !n.is_empty()).collect();
+    if names.contains(&name) {
+        names.push(name);
+        return Err(format!(
+            "Alias '{}' calls itself: {}",
+            name,
+            names.join(" -> ")
+        ));
+    }
+    if names.len() >= MAX_DEPTH {
+        return Err(format!(
+            "Aliases nested more than {} deep: {} -> {}",
+            MAX_DEPTH,
+            names.join(" -> "),
+            name
+        ));
+    }
+    names.push(name);
+    Ok(names.join(","))
+}
+
+/// Run each command of an alias as its own invocation with the same global
+/// flags, stopping at the first that fails
+pub fn run_alias(commands: &[Vec<String>], global_args: &[String], chain: &str) -> ! {
+    let exe = env::current_exe().unwrap_or_else(|_| PathBuf::from("agent-browser"));
+    for command in commands {
+        let status = Command::new(&exe)
+            .args(global_args)
+            .args(command)
+            .env(CHAIN_VAR, chain)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => exit(status.code().unwrap_or(1)),
+            Err(e) => {
+                eprintln!(
+                    "{} Failed to run {}: {}",
+                    color::error_indicator(),
+                    exe.display(),
+                    e
+                );
+                exit(1);
+            }
+        }
+    }
+    exit(0);
+}
+
+/// `agent-browser-<name>` on `PATH`
+pub fn find_external(name: &str) -> Option<PathBuf> {
+    // Names come from the command line; don't let one walk out of PATH
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('-') {
+        return None;
+    }
+    let file = format!("agent-browser-{}{}", name, env::consts::EXE_SUFFIX);
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&file))
+        .find(|path| path.is_file())
+}
+
+/// Run an external subcommand with the session (and config profile)
+/// exported, and exit with its status
+pub fn run_external(path: &PathBuf, args: &[String], flags: &Flags) -> ! {
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .env("AGENT_BROWSER_SESSION", &flags.session);
+    if let Some(ref name) = flags.env {
+        command.env("AGENT_BROWSER_ENV", name);
+    }
+    if let Ok(exe) = env::current_exe() {
+        command.env("AGENT_BROWSER_BIN", exe);
+    }
+    match command.status() {
+        Ok(status) => exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!(
+                "{} Failed to run {}: {}",
+                color::error_indicator(),
+                path.display(),
+                e
+            );
+            exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    const LOGIN: &str = "find label Email fill $1; find label Password fill $2; find role button click --name 'Sign in'";
+
+    #[test]
+    fn test_expand_parameters() {
+        let commands = expand("login", LOGIN, &args("me@example.com hunter2")).unwrap();
+        assert_eq!(
+            commands,
+            vec![
+                args("find label Email fill me@example.com"),
+                args("find label Password fill hunter2"),
+                ["find", "role", "button", "click", "--name", "Sign in"]
+                    .map(String::from)
+                    .to_vec(),
+            ]
+        );
+
+        let err = expand("login", LOGIN, &args("me@example.com")).unwrap_err();
+        assert!(err.contains("login"));
+    }
+
+    #[test]
+    fn test_expand_appends_arguments_without_parameters() {
+        assert_eq!(
+            expand("snap", "snapshot -i -c", &args("-d 3")).unwrap(),
+            vec![args("snapshot -i -c -d 3")]
+        );
+        assert_eq!(
+            expand("each", "open $1; screenshot $@", &args("a.com b.png")).unwrap(),
+            vec![args("open a.com"), args("screenshot a.com b.png")]
+        );
+    }
+
+    #[test]
+    fn test_split_commands_respects_quotes() {
+        assert_eq!(
+            split_commands(r#"eval "a; b"; get title"#),
+            vec![r#"eval "a; b""#, " get title"]
+        );
+        assert_eq!(
+            expand("js", r#"eval 'x = 1; x + $1'"#, &args("2")).unwrap(),
+            vec![["eval", "x = 1; x + 2"].map(String::from).to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_alias_cycles_are_refused() {
+        assert_eq!(push_chain("", "a").unwrap(), "a");
+        assert_eq!(push_chain("a", "b").unwrap(), "a,b");
+        let err = push_chain("a,b", "a").unwrap_err();
+        assert!(err.contains("a -> b -> a"), "{}", err);
+
+        let deep: Vec<String> = (0..MAX_DEPTH).map(|i| format!("a{}", i)).collect();
+        assert!(push_chain(&deep.join(","), "b").is_err());
+    }
+
+    #[test]
+    fn test_find_external_rejects_paths() {
+        assert!(find_external("../evil").is_none());
+        assert!(find_external("").is_none());
+    }
+}
@@ -1,6 +1,12 @@
 use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use crate::flags::Flags;
+use crate::mock::{is_json, load_rules, parse_header, Rule};
+use crate::registry::{self, Invocation};
+use crate::secrets;
+use crate::suggest::{did_you_mean, suggest};
 
 /// Error type for command parsing with contextual information
 #[derive(Debug)]
@@ -10,36 +16,80 @@ pub enum ParseError {
     /// Command exists but subcommand is invalid
     UnknownSubcommand {
         subcommand: String,
-        valid_options: &'static [&'static str],
+        valid_options: Vec<&'static str>,
     },
-    /// Command/subcommand exists but required arguments are missing
-    MissingArguments {
-        context: String,
-        usage: &'static str,
+    /// Flag is not accepted by the command
+    UnknownFlag {
+        flag: String,
+        valid_flags: Vec<&'static str>,
     },
+    /// Command/subcommand exists but required arguments are missing
+    MissingArguments { context: String, usage: String },
     /// Argument exists but has an invalid value
-    InvalidValue {
-        message: String,
-        usage: &'static str,
-    },
+    InvalidValue { message: String, usage: String },
 }
 
 impl ParseError {
+    /// Machine-readable error type for `--json` output
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            ParseError::UnknownCommand { .. } => "unknown_command",
+            ParseError::UnknownSubcommand { .. } => "unknown_subcommand",
+            ParseError::UnknownFlag { .. } => "unknown_flag",
+            ParseError::MissingArguments { .. } => "missing_arguments",
+            ParseError::InvalidValue { .. } => "invalid_value",
+        }
+    }
+
+    /// Close matches for an unknown command, subcommand or flag
+    pub fn suggestions(&self) -> Vec<&'static str> {
+        match self {
+            ParseError::UnknownCommand { command } => {
+                let names: Vec<&'static str> = registry::COMMANDS
+                    .iter()
+                    .flat_map(|c| std::iter::once(c.name).chain(c.aliases.iter().copied()))
+                    .collect();
+                suggest(command, &names)
+            }
+            ParseError::UnknownSubcommand {
+                subcommand,
+                valid_options,
+            } => suggest(subcommand, valid_options),
+            ParseError::UnknownFlag { flag, valid_flags } => suggest(flag, valid_flags),
+            _ => Vec::new(),
+        }
+    }
+
     pub fn format(&self) -> String {
+        self.render(did_you_mean(&self.suggestions()))
+    }
+
+    /// The message without its "Did you mean" hint, which `--json` output
+    /// gives a field of its own
+    pub fn message(&self) -> String {
+        self.render(None)
+    }
+
+    fn render(&self, hint: Option<String>) -> String {
+        let hint = hint.map(|s| format!("\n{}", s)).unwrap_or_default();
         match self {
             ParseError::UnknownCommand { command } => {
-                format!("Unknown command: {}", command)
+                format!("Unknown command: {}{}", command, hint)
             }
             ParseError::UnknownSubcommand {
                 subcommand,
                 valid_options,
             } => {
                 format!(
-                    "Unknown subcommand: {}\nValid options: {}",
+                    "Unknown subcommand: {}{}\nValid options: {}",
                     subcommand,
+                    hint,
                     valid_options.join(", ")
                 )
             }
+            ParseError::UnknownFlag { flag, .. } => {
+                format!("Unknown flag: {}{}", flag, hint)
+            }
             ParseError::MissingArguments { context, usage } => {
                 format!(
                     "Missing arguments for: {}\nUsage: agent-browser {}",
@@ -53,46 +103,141 @@ impl ParseError {
     }
 }
 
+/// Request id unique across CLI processes and threads, so a `cancel` names
+/// exactly one in-flight request
 pub fn gen_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
     format!(
-        "r{}",
+        "r{}-{}-{}",
+        std::process::id(),
         std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
-            .as_micros()
-            % 1000000
+            .as_micros(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
     )
 }
 
-pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError> {
-    if args.is_empty() {
-        return Err(ParseError::MissingArguments {
-            context: "".to_string(),
-            usage: "<command> [args...]",
-        });
+/// A flag's value as a number that fits `T`; the registry only checks that
+/// it's an integer
+fn number<T: std::str::FromStr>(
+    inv: &Invocation,
+    long: &str,
+    path: &str,
+) -> Result<Option<T>, ParseError> {
+    inv.value(long)
+        .map(|v| {
+            v.parse().map_err(|_| ParseError::InvalidValue {
+                message: format!("Invalid value for {}: {}", long, v),
+                usage: registry::usage_of(path),
+            })
+        })
+        .transpose()
+}
+
+/// A positional argument as a number that fits `T`, like `number` for flags
+fn arg_number<T: std::str::FromStr>(
+    inv: &Invocation,
+    i: usize,
+    path: &str,
+) -> Result<Option<T>, ParseError> {
+    inv.arg(i)
+        .map(|v| {
+            v.parse().map_err(|_| {
+                let name = registry::lookup(path)
+                    .and_then(|cmd| cmd.args.get(i))
+                    .map_or("argument", |a| a.name);
+                ParseError::InvalidValue {
+                    message: format!("Invalid value for {}: {}", name, v),
+                    usage: registry::usage_of(path),
+                }
+            })
+        })
+        .transpose()
+}
+
+/// `30s`, `5m`, `1h`, `500ms`, or a bare number of seconds
+fn parse_duration(s: &str) -> Option<std::time::Duration> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (n, unit) = s.split_at(split);
+    let n: u64 = n.parse().ok()?;
+    let ms = match unit {
+        "ms" => n,
+        "" | "s" => n.checked_mul(1000)?,
+        "m" => n.checked_mul(60_000)?,
+        "h" => n.checked_mul(3_600_000)?,
+        _ => return None,
+    };
+    Some(std::time::Duration::from_millis(ms))
+}
+
+/// A status pattern: `422`, `4xx`, `500-599`, or a comma-separated list
+fn is_status_pattern(pattern: &str) -> bool {
+    let code = |s: &str| s.len() == 3 && s.bytes().all(|b| b.is_ascii_digit());
+    pattern.split(',').map(str::trim).all(|part| {
+        let lower = part.to_ascii_lowercase();
+        match lower.split_once('-') {
+            Some((from, to)) => code(from) && code(to),
+            None => {
+                code(&lower)
+                    || (lower.len() == 3
+                        && lower.ends_with("xx")
+                        && matches!(lower.as_bytes()[0], b'1'..=b'5'))
+            }
+        }
+    })
+}
+
+/// Resolve an absolute path such as `/login` against a base URL's origin, as a
+/// link's `href` would be
+fn resolve_path(base: &str, path: &str) -> String {
+    format!("{}{}", origin(base), path)
+}
+
+/// `scheme://host[:port]` part of a URL
+fn origin(url: &str) -> &str {
+    let host_start = url.find("://").map_or(0, |i| i + 3);
+    let origin_end = url[host_start..]
+        .find(['/', '?', '#'])
+        .map_or(url.len(), |i| host_start + i);
+    &url[..origin_end]
+}
+
+/// Prefix `https://` unless the URL already has a scheme
+fn with_scheme(url: &str) -> String {
+    let url_lower = url.to_lowercase();
+    if url_lower.starts_with("http://")
+        || url_lower.starts_with("https://")
+        || url_lower.starts_with("about:")
+        || url_lower.starts_with("data:")
+        || url_lower.starts_with("file:")
+    {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
     }
+}
 
-    let cmd = args[0].as_str();
-    let rest: Vec<&str> = args[1..].iter().map(|s| s.as_str()).collect();
+/// Build the daemon command for `args`. The command line is checked against
+/// the registry first, so builders can rely on the required arguments they
+/// declare being present and well-typed.
+pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError> {
+    let mut inv = registry::parse(args, flags.strict)?;
+    secrets::resolve_invocation(&mut inv).map_err(|message| ParseError::InvalidValue {
+        message,
+        usage: registry::usage_of(&inv.path.join(" ")),
+    })?;
     let id = gen_id();
+    let a = |i: usize| inv.arg(i).unwrap_or_default();
+    let rest = |from: usize| inv.args.get(from..).unwrap_or_default();
 
-    match cmd {
+    match inv.path[..] {
         // === Navigation ===
-        "open" | "goto" | "navigate" => {
-            let url = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: cmd.to_string(),
-                usage: "open <url>",
-            })?;
-            let url_lower = url.to_lowercase();
-            let url = if url_lower.starts_with("http://")
-                || url_lower.starts_with("https://")
-                || url_lower.starts_with("about:")
-                || url_lower.starts_with("data:")
-                || url_lower.starts_with("file:")
-            {
-                url.to_string()
-            } else {
-                format!("https://{}", url)
+        ["open"] => {
+            let url = a(0);
+            let url = match &flags.base_url {
+                Some(base) if url.starts_with('/') => resolve_path(base, url),
+                _ => with_scheme(url),
             };
             let mut nav_cmd = json!({ "id": id, "action": "navigate", "url": url });
             // If --headers flag is set, include headers (scoped to this origin)
@@ -103,252 +248,162 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
             }
             Ok(nav_cmd)
         }
-        "back" => Ok(json!({ "id": id, "action": "back" })),
-        "forward" => Ok(json!({ "id": id, "action": "forward" })),
-        "reload" => Ok(json!({ "id": id, "action": "reload" })),
+        ["fetch"] => {
+            let invalid = |message: String| ParseError::InvalidValue {
+                message,
+                usage: registry::usage_of("fetch"),
+            };
+            // Bare paths go to the base URL, or else the current page's origin
+            let url = match &flags.base_url {
+                Some(base) if a(0).starts_with('/') => resolve_path(base, a(0)),
+                _ if a(0).starts_with('/') => a(0).to_string(),
+                _ => with_scheme(a(0)),
+            };
+            // --headers given with the command is for this request, as with
+            // open; headers from the config or a profile only go to the
+            // profile's base_url
+            let scoped = flags.explicit.contains(&"--headers")
+                || flags
+                    .base_url
+                    .as_deref()
+                    .is_some_and(|base| origin(base).eq_ignore_ascii_case(origin(&url)));
+            let mut headers = serde_json::Map::new();
+            if let Some(headers_json) = flags.headers.as_ref().filter(|_| scoped) {
+                if let Ok(Value::Object(global)) = serde_json::from_str(headers_json) {
+                    headers.extend(global);
+                }
+            }
+            for header in inv.values("--header") {
+                let (name, value) = parse_header(header).map_err(invalid)?;
+                headers.insert(name, json!(value));
+            }
+            let mut cmd = json!({ "id": id, "action": "fetch", "url": url });
+            if let Some(method) = inv.value("--method") {
+                cmd["method"] = json!(method.to_uppercase());
+            }
+            match (inv.value("--data"), inv.value("--data-file")) {
+                (Some(_), Some(_)) => {
+                    return Err(invalid(
+                        "Use either --data or --data-file, not both".to_string(),
+                    ))
+                }
+                (Some(data), None) => {
+                    let has_type = headers
+                        .keys()
+                        .any(|k| k.eq_ignore_ascii_case("content-type"));
+                    if !has_type && is_json(data) {
+                        headers.insert("Content-Type".to_string(), json!("application/json"));
+                    }
+                    cmd["data"] = json!(data);
+                }
+                (None, Some(file)) => {
+                    let file = std::path::absolute(file)
+                        .map_err(|e| invalid(format!("Invalid path {}: {}", file, e)))?;
+                    if !file.is_file() {
+                        return Err(invalid(format!("File not found: {}", file.display())));
+                    }
+                    cmd["dataFile"] = json!(file.to_string_lossy());
+                }
+                (None, None) => {}
+            }
+            if !headers.is_empty() {
+                cmd["headers"] = Value::Object(headers);
+            }
+            if let Some(out) = inv.value("--out") {
+                let out = std::path::absolute(out)
+                    .map_err(|e| invalid(format!("Invalid path {}: {}", out, e)))?;
+                cmd["path"] = json!(out.to_string_lossy());
+            }
+            Ok(cmd)
+        }
+        ["back"] => Ok(json!({ "id": id, "action": "back" })),
+        ["forward"] => Ok(json!({ "id": id, "action": "forward" })),
+        ["reload"] => Ok(json!({ "id": id, "action": "reload" })),
 
         // === Core Actions ===
-        "click" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "click".to_string(),
-                usage: "click <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "click", "selector": sel }))
-        }
-        "dblclick" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "dblclick".to_string(),
-                usage: "dblclick <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "dblclick", "selector": sel }))
-        }
-        "fill" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "fill".to_string(),
-                usage: "fill <selector> <text>",
-            })?;
-            Ok(json!({ "id": id, "action": "fill", "selector": sel, "value": rest[1..].join(" ") }))
-        }
-        "type" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "type".to_string(),
-                usage: "type <selector> <text>",
-            })?;
-            Ok(json!({ "id": id, "action": "type", "selector": sel, "text": rest[1..].join(" ") }))
-        }
-        "hover" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "hover".to_string(),
-                usage: "hover <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "hover", "selector": sel }))
+        ["click" | "dblclick" | "hover" | "focus" | "check" | "uncheck" | "scrollintoview"
+        | "highlight"] => Ok(json!({ "id": id, "action": inv.action, "selector": a(0) })),
+        ["fill"] => {
+            Ok(json!({ "id": id, "action": "fill", "selector": a(0), "value": rest(1).join(" ") }))
         }
-        "focus" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "focus".to_string(),
-                usage: "focus <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "focus", "selector": sel }))
-        }
-        "check" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "check".to_string(),
-                usage: "check <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "check", "selector": sel }))
-        }
-        "uncheck" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "uncheck".to_string(),
-                usage: "uncheck <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "uncheck", "selector": sel }))
+        ["type"] => {
+            Ok(json!({ "id": id, "action": "type", "selector": a(0), "text": rest(1).join(" ") }))
         }
-        "select" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "select".to_string(),
-                usage: "select <selector> <value...>",
-            })?;
-            let _val = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "select".to_string(),
-                usage: "select <selector> <value...>",
-            })?;
-            let values = &rest[1..];
+        ["select"] => {
+            let values = rest(1);
             if values.len() == 1 {
-                Ok(json!({ "id": id, "action": "select", "selector": sel, "values": values[0] }))
+                Ok(json!({ "id": id, "action": "select", "selector": a(0), "values": values[0] }))
             } else {
-                Ok(json!({ "id": id, "action": "select", "selector": sel, "values": values }))
+                Ok(json!({ "id": id, "action": "select", "selector": a(0), "values": values }))
             }
         }
-        "drag" => {
-            let src = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "drag".to_string(),
-                usage: "drag <source> <target>",
-            })?;
-            let tgt = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "drag".to_string(),
-                usage: "drag <source> <target>",
-            })?;
-            Ok(json!({ "id": id, "action": "drag", "source": src, "target": tgt }))
-        }
-        "upload" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "upload".to_string(),
-                usage: "upload <selector> <files...>",
-            })?;
-            Ok(json!({ "id": id, "action": "upload", "selector": sel, "files": &rest[1..] }))
+        ["drag"] => Ok(json!({ "id": id, "action": "drag", "source": a(0), "target": a(1) })),
+        ["upload"] => {
+            Ok(json!({ "id": id, "action": "upload", "selector": a(0), "files": rest(1) }))
         }
-        "download" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "download".to_string(),
-                usage: "download <selector> <path>",
-            })?;
-            Ok(json!({ "id": id, "action": "download", "selector": sel, "path": path }))
+        ["download"] => {
+            Ok(json!({ "id": id, "action": "download", "selector": a(0), "path": a(1) }))
         }
 
         // === Keyboard ===
-        "press" | "key" => {
-            let key = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "press".to_string(),
-                usage: "press <key>",
-            })?;
-            Ok(json!({ "id": id, "action": "press", "key": key }))
-        }
-        "keydown" => {
-            let key = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "keydown".to_string(),
-                usage: "keydown <key>",
-            })?;
-            Ok(json!({ "id": id, "action": "keydown", "key": key }))
-        }
-        "keyup" => {
-            let key = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "keyup".to_string(),
-                usage: "keyup <key>",
-            })?;
-            Ok(json!({ "id": id, "action": "keyup", "key": key }))
+        ["press" | "keydown" | "keyup"] => {
+            Ok(json!({ "id": id, "action": inv.action, "key": a(0) }))
         }
 
         // === Scroll ===
-        "scroll" => {
-            let dir = rest.get(0).unwrap_or(&"down");
-            let amount = rest
-                .get(1)
-                .and_then(|s| s.parse::<i32>().ok())
-                .unwrap_or(300);
+        ["scroll"] => {
+            let dir = inv.arg(0).unwrap_or("down");
+            let amount = arg_number::<i32>(&inv, 1, "scroll")?.unwrap_or(300);
             Ok(json!({ "id": id, "action": "scroll", "direction": dir, "amount": amount }))
         }
-        "scrollintoview" | "scrollinto" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "scrollintoview".to_string(),
-                usage: "scrollintoview <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "scrollintoview", "selector": sel }))
-        }
 
         // === Wait ===
-        "wait" => {
-            // Check for --url flag: wait --url "**/dashboard"
-            if let Some(idx) = rest.iter().position(|&s| s == "--url" || s == "-u") {
-                let url = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --url".to_string(),
-                        usage: "wait --url <pattern>",
-                    })?;
+        ["wait"] => {
+            if let Some(url) = inv.value("--url") {
                 return Ok(json!({ "id": id, "action": "waitforurl", "url": url }));
             }
-
-            // Check for --load flag: wait --load networkidle
-            if let Some(idx) = rest.iter().position(|&s| s == "--load" || s == "-l") {
-                let state = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --load".to_string(),
-                        usage: "wait --load <state>",
-                    })?;
+            if let Some(state) = inv.value("--load") {
                 return Ok(json!({ "id": id, "action": "waitforloadstate", "state": state }));
             }
-
-            // Check for --fn flag: wait --fn "window.ready === true"
-            if let Some(idx) = rest.iter().position(|&s| s == "--fn" || s == "-f") {
-                let expr = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --fn".to_string(),
-                        usage: "wait --fn <expression>",
-                    })?;
+            if let Some(expr) = inv.value("--fn") {
                 return Ok(json!({ "id": id, "action": "waitforfunction", "expression": expr }));
             }
-
-            // Check for --text flag: wait --text "Welcome"
-            if let Some(idx) = rest.iter().position(|&s| s == "--text" || s == "-t") {
-                let text = rest
-                    .get(idx + 1)
-                    .ok_or_else(|| ParseError::MissingArguments {
-                        context: "wait --text".to_string(),
-                        usage: "wait --text <text>",
-                    })?;
+            if let Some(text) = inv.value("--text") {
                 // Use getByText locator to wait for text to appear
                 return Ok(
                     json!({ "id": id, "action": "wait", "selector": format!("text={}", text) }),
                 );
             }
-
-            // Check for --download flag: wait --download [path] [--timeout ms]
-            if rest.iter().any(|&s| s == "--download" || s == "-d") {
+            if inv.has("--download") {
                 let mut cmd = json!({ "id": id, "action": "waitfordownload" });
-                // Check for optional path (first non-flag argument after --download)
-                let download_idx = rest
-                    .iter()
-                    .position(|&s| s == "--download" || s == "-d")
-                    .unwrap();
-                if let Some(path) = rest.get(download_idx + 1) {
-                    if !path.starts_with("--") {
-                        cmd["path"] = json!(path);
-                    }
+                if let Some(path) = inv.arg(0) {
+                    cmd["path"] = json!(path);
                 }
-                // Check for optional timeout
-                if let Some(idx) = rest.iter().position(|&s| s == "--timeout") {
-                    if let Some(timeout_str) = rest.get(idx + 1) {
-                        if let Ok(timeout) = timeout_str.parse::<u64>() {
-                            cmd["timeout"] = json!(timeout);
-                        }
-                    }
+                if let Some(timeout) = number::<u64>(&inv, "--timeout", "wait")? {
+                    cmd["timeout"] = json!(timeout);
                 }
                 return Ok(cmd);
             }
 
             // Default: selector or timeout
-            if let Some(arg) = rest.get(0) {
-                if arg.parse::<u64>().is_ok() {
-                    Ok(
-                        json!({ "id": id, "action": "wait", "timeout": arg.parse::<u64>().unwrap() }),
-                    )
-                } else {
-                    Ok(json!({ "id": id, "action": "wait", "selector": arg }))
-                }
-            } else {
-                Err(ParseError::MissingArguments {
-                    context: "wait".to_string(),
-                    usage: "wait <selector|ms|--url|--load|--fn|--text>",
-                })
+            match inv.arg(0) {
+                Some(arg) => match arg.parse::<u64>() {
+                    Ok(ms) => Ok(json!({ "id": id, "action": "wait", "timeout": ms })),
+                    Err(_) => Ok(json!({ "id": id, "action": "wait", "selector": arg })),
+                },
+                None => Err(registry::missing("wait")),
             }
         }
 
         // === Screenshot/PDF ===
-        "screenshot" => {
+        ["screenshot"] => {
             // screenshot [selector] [path]
             // selector: @ref or CSS selector
             // path: file path (contains / or . or ends with known extension)
-            let (selector, path) = match (rest.get(0), rest.get(1)) {
+            let (selector, path) = match (inv.arg(0), inv.arg(1)) {
                 (Some(first), Some(second)) => {
                     // Two args: first is selector, second is path
-                    (Some(*first), Some(*second))
+                    (Some(first), Some(second))
                 }
                 (Some(first), None) => {
                     // One arg: determine if it's a selector or a path
@@ -363,9 +418,9 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                         || first.ends_with(".webp");
                     let is_path = is_relative_path || first.contains('/') || has_path_extension;
                     if is_selector || !is_path {
-                        (Some(*first), None)
+                        (Some(first), None)
                     } else {
-                        (None, Some(*first))
+                        (None, Some(first))
                     }
                 }
                 _ => (None, None),
@@ -374,947 +429,523 @@ pub fn parse_command(args: &[String], flags: &Flags) -> Result<Value, ParseError
                 json!({ "id": id, "action": "screenshot", "path": path, "selector": selector, "fullPage": flags.full }),
             )
         }
-        "pdf" => {
-            let path = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "pdf".to_string(),
-                usage: "pdf <path>",
-            })?;
-            Ok(json!({ "id": id, "action": "pdf", "path": path }))
-        }
+        ["pdf"] => Ok(json!({ "id": id, "action": "pdf", "path": a(0) })),
 
         // === Snapshot ===
-        "snapshot" => {
+        ["snapshot"] => {
             let mut cmd = json!({ "id": id, "action": "snapshot" });
-            let obj = cmd.as_object_mut().unwrap();
-            let mut i = 0;
-            while i < rest.len() {
-                match rest[i] {
-                    "-i" | "--interactive" => {
-                        obj.insert("interactive".to_string(), json!(true));
-                    }
-                    "-c" | "--compact" => {
-                        obj.insert("compact".to_string(), json!(true));
-                    }
-                    "-d" | "--depth" => {
-                        if let Some(d) = rest.get(i + 1) {
-                            if let Ok(n) = d.parse::<i32>() {
-                                obj.insert("maxDepth".to_string(), json!(n));
-                                i += 1;
-                            }
-                        }
-                    }
-                    "-s" | "--selector" => {
-                        if let Some(s) = rest.get(i + 1) {
-                            obj.insert("selector".to_string(), json!(s));
-                            i += 1;
-                        }
-                    }
-                    _ => {}
-                }
-                i += 1;
+            if inv.has("--interactive") {
+                cmd["interactive"] = json!(true);
+            }
+            if inv.has("--compact") {
+                cmd["compact"] = json!(true);
+            }
+            if let Some(depth) = number::<i32>(&inv, "--depth", "snapshot")? {
+                cmd["maxDepth"] = json!(depth);
+            }
+            if let Some(selector) = inv.value("--selector") {
+                cmd["selector"] = json!(selector);
             }
             Ok(cmd)
         }
 
         // === Eval ===
-        "eval" => Ok(json!({ "id": id, "action": "evaluate", "script": rest.join(" ") })),
+        ["eval"] => Ok(json!({ "id": id, "action": "evaluate", "script": rest(0).join(" ") })),
 
         // === Close ===
-        "close" | "quit" | "exit" => Ok(json!({ "id": id, "action": "close" })),
+        ["close"] => Ok(json!({ "id": id, "action": "close" })),
 
         // === Connect (CDP) ===
-        "connect" => {
-            let endpoint = rest.first().ok_or_else(|| ParseError::MissingArguments {
-                context: "connect".to_string(),
-                usage: "connect <port|url>",
-            })?;
+        ["connect"] => {
+            let endpoint = a(0);
             // Check if it's a URL (ws://, wss://, http://, https://)
             if endpoint.starts_with("ws://")
                 || endpoint.starts_with("wss://")
                 || endpoint.starts_with("http://")
                 || endpoint.starts_with("https://")
             {
-                Ok(json!({ "id": id, "action": "launch", "cdpUrl": endpoint }))
-            } else {
-                // It's a port number - validate and use cdpPort field
-                let port: u16 = match endpoint.parse::<u32>() {
-                    Ok(p) if p == 0 => {
-                        return Err(ParseError::InvalidValue {
-                            message: "Invalid port: port must be greater than 0".to_string(),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                    Ok(p) if p > 65535 => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!(
-                                "Invalid port: {} is out of range (valid range: 1-65535)",
-                                p
-                            ),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                    Ok(p) => p as u16,
-                    Err(_) => {
-                        return Err(ParseError::InvalidValue {
-                            message: format!(
-                                "Invalid value: '{}' is not a valid port number or URL",
-                                endpoint
-                            ),
-                            usage: "connect <port|url>",
-                        });
-                    }
-                };
-                Ok(json!({ "id": id, "action": "launch", "cdpPort": port }))
+                return Ok(json!({ "id": id, "action": "launch", "cdpUrl": endpoint }));
             }
+            // It's a port number - validate and use cdpPort field
+            let message = match endpoint.parse::<u32>() {
+                Ok(0) => "Invalid port: port must be greater than 0".to_string(),
+                Ok(p) if p > 65535 => {
+                    format!("Invalid port: {} is out of range (valid range: 1-65535)", p)
+                }
+                Ok(p) => return Ok(json!({ "id": id, "action": "launch", "cdpPort": p })),
+                Err(_) => format!(
+                    "Invalid value: '{}' is not a valid port number or URL",
+                    endpoint
+                ),
+            };
+            Err(ParseError::InvalidValue {
+                message,
+                usage: registry::usage_of("connect"),
+            })
         }
 
         // === Get ===
-        "get" => parse_get(&rest, &id),
+        ["get", "text"] => Ok(json!({ "id": id, "action": "gettext", "selector": a(0) })),
+        ["get", "html"] => Ok(json!({ "id": id, "action": "innerhtml", "selector": a(0) })),
+        ["get", "value"] => Ok(json!({ "id": id, "action": "inputvalue", "selector": a(0) })),
+        ["get", "attr"] => {
+            Ok(json!({ "id": id, "action": "getattribute", "selector": a(0), "attribute": a(1) }))
+        }
+        ["get", "url"] => Ok(json!({ "id": id, "action": "url" })),
+        ["get", "title"] => Ok(json!({ "id": id, "action": "title" })),
+        ["get", "count"] => Ok(json!({ "id": id, "action": "count", "selector": a(0) })),
+        ["get", "box"] => Ok(json!({ "id": id, "action": "boundingbox", "selector": a(0) })),
+        ["get", "styles"] => Ok(json!({ "id": id, "action": "styles", "selector": a(0) })),
 
         // === Is (state checks) ===
-        "is" => parse_is(&rest, &id),
+        ["is", _] => Ok(json!({ "id": id, "action": inv.action, "selector": a(0) })),
 
         // === Find (locators) ===
-        "find" => parse_find(&rest, &id),
+        ["find", locator] => parse_find(locator, &inv, &id),
 
         // === Mouse ===
-        "mouse" => parse_mouse(&rest, &id),
-
-        // === Set (browser settings) ===
-        "set" => parse_set(&rest, &id),
-
-        // === Network ===
-        "network" => parse_network(&rest, &id),
-
-        // === Storage ===
-        "storage" => parse_storage(&rest, &id),
-
-        // === Cookies ===
-        "cookies" => {
-            let op = rest.get(0).unwrap_or(&"get");
-            match *op {
-                "set" => {
-                    let name = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "cookies set".to_string(),
-                        usage: "cookies set <name> <value> [--url <url>] [--domain <domain>] [--path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]",
-                    })?;
-                    let value = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                        context: "cookies set".to_string(),
-                        usage: "cookies set <name> <value> [--url <url>] [--domain <domain>] [--path <path>] [--httpOnly] [--secure] [--sameSite <Strict|Lax|None>] [--expires <timestamp>]",
-                    })?;
-
-                    let mut cookie = json!({ "name": name, "value": value });
-
-                    // Parse optional flags
-                    let mut i = 3;
-                    while i < rest.len() {
-                        match rest[i] {
-                            "--url" => {
-                                if let Some(url) = rest.get(i + 1) {
-                                    cookie["url"] = json!(url);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --url".to_string(),
-                                        usage: "--url <url>",
-                                    });
-                                }
-                            }
-                            "--domain" => {
-                                if let Some(domain) = rest.get(i + 1) {
-                                    cookie["domain"] = json!(domain);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --domain".to_string(),
-                                        usage: "--domain <domain>",
-                                    });
-                                }
-                            }
-                            "--path" => {
-                                if let Some(path) = rest.get(i + 1) {
-                                    cookie["path"] = json!(path);
-                                    i += 2;
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --path".to_string(),
-                                        usage: "--path <path>",
-                                    });
-                                }
-                            }
-                            "--httpOnly" => {
-                                cookie["httpOnly"] = json!(true);
-                                i += 1;
-                            }
-                            "--secure" => {
-                                cookie["secure"] = json!(true);
-                                i += 1;
-                            }
-                            "--sameSite" => {
-                                if let Some(same_site) = rest.get(i + 1) {
-                                    // Validate sameSite value
-                                    if *same_site == "Strict"
-                                        || *same_site == "Lax"
-                                        || *same_site == "None"
-                                    {
-                                        cookie["sameSite"] = json!(same_site);
-                                        i += 2;
-                                    } else {
-                                        return Err(ParseError::MissingArguments {
-                                            context: "cookies set --sameSite".to_string(),
-                                            usage: "--sameSite <Strict|Lax|None>",
-                                        });
-                                    }
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --sameSite".to_string(),
-                                        usage: "--sameSite <Strict|Lax|None>",
-                                    });
-                                }
-                            }
-                            "--expires" => {
-                                if let Some(expires_str) = rest.get(i + 1) {
-                                    if let Ok(expires) = expires_str.parse::<i64>() {
-                                        cookie["expires"] = json!(expires);
-                                        i += 2;
-                                    } else {
-                                        return Err(ParseError::MissingArguments {
-                                            context: "cookies set --expires".to_string(),
-                                            usage: "--expires <timestamp>",
-                                        });
-                                    }
-                                } else {
-                                    return Err(ParseError::MissingArguments {
-                                        context: "cookies set --expires".to_string(),
-                                        usage: "--expires <timestamp>",
-                                    });
-                                }
-                            }
-                            _ => {
-                                // Unknown flag, skip it (or could error)
-                                i += 1;
-                            }
-                        }
-                    }
-
-                    Ok(json!({ "id": id, "action": "cookies_set", "cookies": [cookie] }))
-                }
-                "clear" => Ok(json!({ "id": id, "action": "cookies_clear" })),
-                _ => Ok(json!({ "id": id, "action": "cookies_get" })),
-            }
+        ["mouse", "move"] => Ok(json!({
+            "id": id,
+            "action": "mousemove",
+            "x": arg_number::<i32>(&inv, 0, "mouse move")?,
+            "y": arg_number::<i32>(&inv, 1, "mouse move")?
+        })),
+        ["mouse", "down"] => {
+            Ok(json!({ "id": id, "action": "mousedown", "button": inv.arg(0).unwrap_or("left") }))
         }
-
-        // === Tabs ===
-        "tab" => match rest.get(0).map(|s| *s) {
-            Some("new") => {
-                let mut cmd = json!({ "id": id, "action": "tab_new" });
-                if let Some(url) = rest.get(1) {
-                    cmd["url"] = json!(url);
-                }
-                Ok(cmd)
-            }
-            Some("list") => Ok(json!({ "id": id, "action": "tab_list" })),
-            Some("close") => {
-                let mut cmd = json!({ "id": id, "action": "tab_close" });
-                if let Some(index) = rest.get(1).and_then(|s| s.parse::<i32>().ok()) {
-                    cmd["index"] = json!(index);
-                }
-                Ok(cmd)
-            }
-            Some(n) if n.parse::<i32>().is_ok() => {
-                Ok(json!({ "id": id, "action": "tab_switch", "index": n.parse::<i32>().unwrap() }))
-            }
-            _ => Ok(json!({ "id": id, "action": "tab_list" })),
-        },
-
-        // === Window ===
-        "window" => {
-            const VALID: &[&str] = &["new"];
-            match rest.get(0).map(|s| *s) {
-                Some("new") => Ok(json!({ "id": id, "action": "window_new" })),
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "window".to_string(),
-                    usage: "window <new>",
-                }),
-            }
+        ["mouse", "up"] => {
+            Ok(json!({ "id": id, "action": "mouseup", "button": inv.arg(0).unwrap_or("left") }))
         }
-
-        // === Frame ===
-        "frame" => {
-            if rest.get(0).map(|s| *s) == Some("main") {
-                Ok(json!({ "id": id, "action": "mainframe" }))
-            } else {
-                let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                    context: "frame".to_string(),
-                    usage: "frame <selector|main>",
-                })?;
-                Ok(json!({ "id": id, "action": "frame", "selector": sel }))
-            }
+        ["mouse", "wheel"] => {
+            let dy = arg_number::<i32>(&inv, 0, "mouse wheel")?.unwrap_or(100);
+            let dx = arg_number::<i32>(&inv, 1, "mouse wheel")?.unwrap_or(0);
+            Ok(json!({ "id": id, "action": "wheel", "deltaX": dx, "deltaY": dy }))
         }
 
-        // === Dialog ===
-        "dialog" => {
-            const VALID: &[&str] = &["accept", "dismiss"];
-            match rest.get(0).map(|s| *s) {
-                Some("accept") => {
-                    let mut cmd = json!({ "id": id, "action": "dialog", "response": "accept" });
-                    if let Some(prompt_text) = rest.get(1) {
-                        cmd["promptText"] = json!(prompt_text);
-                    }
-                    Ok(cmd)
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "dialog".to_string(),
-                    usage: "dialog <accept|dismiss> [text]",
-                }),
-            }
+        // === Set (browser settings) ===
+        ["set", "viewport"] => Ok(json!({
+            "id": id,
+            "action": "viewport",
+            "width": arg_number::<i32>(&inv, 0, "set viewport")?,
+            "height": arg_number::<i32>(&inv, 1, "set viewport")?
+        })),
+        ["set", "device"] => Ok(json!({ "id": id, "action": "device", "device": a(0) })),
+        ["set", "geo"] => Ok(json!({
+            "id": id,
+            "action": "geolocation",
+            "latitude": a(0).parse::<f64>().unwrap(),
+            "longitude": a(1).parse::<f64>().unwrap()
+        })),
+        ["set", "offline"] => {
+            let off = !matches!(inv.arg(0), Some("off" | "false"));
+            Ok(json!({ "id": id, "action": "offline", "offline": off }))
         }
-
-        // === Debug ===
-        "trace" => {
-            const VALID: &[&str] = &["start", "stop"];
-            match rest.get(0).map(|s| *s) {
-                Some("start") => Ok(json!({ "id": id, "action": "trace_start" })),
-                Some("stop") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "trace stop".to_string(),
-                        usage: "trace stop <path>",
-                    })?;
-                    Ok(json!({ "id": id, "action": "trace_stop", "path": path }))
+        ["set", "network"] => {
+            let mut cmd = json!({ "id": id, "action": "network_conditions", "profile": a(0) });
+            let custom = [
+                ("--down", "downloadKbps"),
+                ("--up", "uploadKbps"),
+                ("--latency", "latency"),
+            ];
+            let mut given = false;
+            for (flag, key) in custom {
+                let Some(value) = number::<u64>(&inv, flag, "set network")? else {
+                    continue;
+                };
+                if a(0) != "custom" {
+                    return Err(ParseError::InvalidValue {
+                        message: format!("{} only applies to the custom profile", flag),
+                        usage: registry::usage_of("set network"),
+                    });
                 }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "trace".to_string(),
-                    usage: "trace <start|stop> [path]",
-                }),
+                cmd[key] = json!(value);
+                given = true;
             }
+            if a(0) == "custom" && !given {
+                return Err(ParseError::InvalidValue {
+                    message: "custom needs at least one of --down, --up or --latency".to_string(),
+                    usage: registry::usage_of("set network"),
+                });
+            }
+            Ok(cmd)
         }
-
-        // === Recording (Playwright native video recording) ===
-        "record" => {
-            const VALID: &[&str] = &["start", "stop", "restart"];
-            match rest.get(0).map(|s| *s) {
-                Some("start") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "record start".to_string(),
-                        usage: "record start <output.webm> [url]",
-                    })?;
-                    // Optional URL parameter
-                    let url = rest.get(2);
-                    let mut cmd = json!({ "id": id, "action": "recording_start", "path": path });
-                    if let Some(u) = url {
-                        // Add https:// prefix if needed
-                        let url_str = if u.starts_with("http") {
-                            u.to_string()
-                        } else {
-                            format!("https://{}", u)
-                        };
-                        cmd["url"] = json!(url_str);
-                    }
-                    Ok(cmd)
-                }
-                Some("stop") => Ok(json!({ "id": id, "action": "recording_stop" })),
-                Some("restart") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "record restart".to_string(),
-                        usage: "record restart <output.webm> [url]",
-                    })?;
-                    // Optional URL parameter
-                    let url = rest.get(2);
-                    let mut cmd = json!({ "id": id, "action": "recording_restart", "path": path });
-                    if let Some(u) = url {
-                        // Add https:// prefix if needed
-                        let url_str = if u.starts_with("http") {
-                            u.to_string()
-                        } else {
-                            format!("https://{}", u)
-                        };
-                        cmd["url"] = json!(url_str);
-                    }
-                    Ok(cmd)
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "record".to_string(),
-                    usage: "record <start|stop|restart> [path] [url]",
-                }),
+        ["set", "cpu"] => {
+            let rate = a(0).parse::<f64>().unwrap();
+            if rate < 1.0 {
+                return Err(ParseError::InvalidValue {
+                    message: format!("CPU slowdown rate must be at least 1, got {}", rate),
+                    usage: registry::usage_of("set cpu"),
+                });
             }
+            Ok(json!({ "id": id, "action": "cpu_throttle", "rate": rate }))
         }
-        "console" => {
-            let clear = rest.iter().any(|&s| s == "--clear");
-            Ok(json!({ "id": id, "action": "console", "clear": clear }))
+        ["set", "headers"] => {
+            let headers: Value = serde_json::from_str(a(0)).unwrap();
+            Ok(json!({ "id": id, "action": "headers", "headers": headers }))
         }
-        "errors" => {
-            let clear = rest.iter().any(|&s| s == "--clear");
-            Ok(json!({ "id": id, "action": "errors", "clear": clear }))
+        ["set", "credentials"] => {
+            Ok(json!({ "id": id, "action": "credentials", "username": a(0), "password": a(1) }))
         }
-        "highlight" => {
-            let sel = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-                context: "highlight".to_string(),
-                usage: "highlight <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "highlight", "selector": sel }))
+        ["set", "media"] => {
+            let has = |word: &str| inv.args.iter().any(|s| s == word);
+            let color = if has("dark") {
+                "dark"
+            } else if has("light") {
+                "light"
+            } else {
+                "no-preference"
+            };
+            let reduced = if has("reduced-motion") {
+                "reduce"
+            } else {
+                "no-preference"
+            };
+            Ok(
+                json!({ "id": id, "action": "emulatemedia", "colorScheme": color, "reducedMotion": reduced }),
+            )
         }
 
-        // === State ===
-        "state" => {
-            const VALID: &[&str] = &["save", "load"];
-            match rest.get(0).map(|s| *s) {
-                Some("save") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "state save".to_string(),
-                        usage: "state save <path>",
-                    })?;
-                    Ok(json!({ "id": id, "action": "state_save", "path": path }))
-                }
-                Some("load") => {
-                    let path = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                        context: "state load".to_string(),
-                        usage: "state load <path>",
-                    })?;
-                    Ok(json!({ "id": id, "action": "state_load", "path": path }))
-                }
-                Some(sub) => Err(ParseError::UnknownSubcommand {
-                    subcommand: sub.to_string(),
-                    valid_options: VALID,
-                }),
-                None => Err(ParseError::MissingArguments {
-                    context: "state".to_string(),
-                    usage: "state <save|load> <path>",
-                }),
+        // === Network ===
+        ["network", "route"] => {
+            let invalid = |message: String| ParseError::InvalidValue {
+                message,
+                usage: registry::usage_of("network route"),
+            };
+            let mut rule = Rule {
+                url: a(0).to_string(),
+                method: inv.value("--method").map(String::from),
+                abort: inv.has("--abort"),
+                status: number(&inv, "--status", "network route")?,
+                content_type: inv.value("--content-type").map(String::from),
+                body: inv.value("--body").map(|b| b.into()),
+                body_file: inv.value("--body-file").map(PathBuf::from),
+                delay: number(&inv, "--delay", "network route")?,
+                times: number(&inv, "--times", "network route")?,
+                ..Rule::default()
+            };
+            for header in inv.values("--header") {
+                let (name, value) = parse_header(header).map_err(invalid)?;
+                rule.headers.insert(name, value);
             }
+            let mut cmd = rule.to_route(Path::new(".")).map_err(invalid)?;
+            cmd["id"] = json!(id);
+            cmd["action"] = json!("route");
+            Ok(cmd)
         }
-
-        _ => Err(ParseError::UnknownCommand {
-            command: cmd.to_string(),
-        }),
-    }
-}
-
-fn parse_get(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "text", "html", "value", "attr", "url", "title", "count", "box", "styles",
-    ];
-
-    match rest.get(0).map(|s| *s) {
-        Some("text") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get text".to_string(),
-                usage: "get text <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "gettext", "selector": sel }))
-        }
-        Some("html") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get html".to_string(),
-                usage: "get html <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "innerhtml", "selector": sel }))
+        ["network", "mock"] => {
+            let routes =
+                load_rules(Path::new(a(0))).map_err(|message| ParseError::InvalidValue {
+                    message,
+                    usage: registry::usage_of("network mock"),
+                })?;
+            Ok(json!({ "id": id, "action": "mock", "rules": routes }))
         }
-        Some("value") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get value".to_string(),
-                usage: "get value <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "inputvalue", "selector": sel }))
+        ["network", "unroute"] => {
+            let mut cmd = json!({ "id": id, "action": "unroute" });
+            if let Some(url) = inv.arg(0) {
+                cmd["url"] = json!(url);
+            }
+            Ok(cmd)
         }
-        Some("attr") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get attr".to_string(),
-                usage: "get attr <selector> <attribute>",
-            })?;
-            let attr = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "get attr".to_string(),
-                usage: "get attr <selector> <attribute>",
-            })?;
-            Ok(json!({ "id": id, "action": "getattribute", "selector": sel, "attribute": attr }))
+        ["network", "block"] => {
+            let list = |flag: &str| -> Vec<String> {
+                inv.values(flag)
+                    .flat_map(|v| v.split(','))
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+                    .collect()
+            };
+            // Neither flag: the daemon reports what is blocked
+            let (types, domains) = (list("--types"), list("--domains"));
+            if let Some(bad) = types
+                .iter()
+                .find(|t| !registry::RESOURCE_TYPES.contains(&t.as_str()))
+            {
+                return Err(ParseError::InvalidValue {
+                    message: format!(
+                        "Unknown resource type: {} (expected {})",
+                        bad,
+                        registry::RESOURCE_TYPES.join(", ")
+                    ),
+                    usage: registry::usage_of("network block"),
+                });
+            }
+            let mut cmd = json!({ "id": id, "action": "block" });
+            if !types.is_empty() {
+                cmd["types"] = json!(types);
+            }
+            if !domains.is_empty() {
+                cmd["domains"] = json!(domains);
+            }
+            Ok(cmd)
         }
-        Some("url") => Ok(json!({ "id": id, "action": "url" })),
-        Some("title") => Ok(json!({ "id": id, "action": "title" })),
-        Some("count") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get count".to_string(),
-                usage: "get count <selector>",
+        ["network", "unblock"] => Ok(json!({ "id": id, "action": "unblock" })),
+        ["network", "replay"] => {
+            // The daemon may run in another directory; give it an absolute path
+            let path = std::path::absolute(a(0)).map_err(|e| ParseError::InvalidValue {
+                message: format!("Invalid HAR path {}: {}", a(0), e),
+                usage: registry::usage_of("network replay"),
             })?;
-            Ok(json!({ "id": id, "action": "count", "selector": sel }))
+            let mut cmd = json!({
+                "id": id,
+                "action": "har_replay",
+                "path": path.to_string_lossy(),
+            });
+            if let Some(mode) = inv.value("--not-found") {
+                cmd["notFound"] = json!(mode);
+            }
+            if let Some(url) = inv.value("--url") {
+                cmd["url"] = json!(url);
+            }
+            Ok(cmd)
         }
-        Some("box") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get box".to_string(),
-                usage: "get box <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "boundingbox", "selector": sel }))
+        ["network", "body"] | ["network", "wait-response"] => {
+            let mut cmd = json!({ "id": id, "action": inv.action });
+            match a(0).parse::<u64>() {
+                Ok(index) if inv.path[1] == "body" => cmd["index"] = json!(index),
+                _ => cmd["url"] = json!(a(0)),
+            }
+            if let Some(timeout) = number::<u64>(&inv, "--timeout", "network wait-response")? {
+                cmd["timeout"] = json!(timeout);
+            }
+            if let Some(out) = inv.value("--out") {
+                // The daemon writes the file from its own directory
+                let out = std::path::absolute(out).map_err(|e| ParseError::InvalidValue {
+                    message: format!("Invalid path {}: {}", out, e),
+                    usage: registry::usage_of(&inv.path.join(" ")),
+                })?;
+                cmd["path"] = json!(out.to_string_lossy());
+            }
+            Ok(cmd)
         }
-        Some("styles") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "get styles".to_string(),
-                usage: "get styles <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "styles", "selector": sel }))
+        ["network", "requests"] => {
+            let invalid = |message: String| ParseError::InvalidValue {
+                message,
+                usage: registry::usage_of("network requests"),
+            };
+            let mut cmd = json!({ "id": id, "action": "requests", "clear": inv.has("--clear") });
+            if let Some(f) = inv.value("--filter") {
+                cmd["filter"] = json!(f);
+            }
+            if let Some(method) = inv.value("--method") {
+                cmd["method"] = json!(method.to_uppercase());
+            }
+            if let Some(ty) = inv.value("--type") {
+                cmd["resourceType"] = json!(ty);
+            }
+            if let Some(status) = inv.value("--status") {
+                if !is_status_pattern(status) {
+                    return Err(invalid(format!(
+                        "Invalid status pattern: {} (expected e.g. 422, 4xx, 500-599 or 401,403)",
+                        status
+                    )));
+                }
+                cmd["status"] = json!(status);
+            }
+            if let Some(since) = inv.value("--since") {
+                let ago = parse_duration(since).ok_or_else(|| {
+                    invalid(format!(
+                        "Invalid duration: {} (expected e.g. 30s, 5m)",
+                        since
+                    ))
+                })?;
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap();
+                cmd["since"] = json!(now.saturating_sub(ago).as_millis() as u64);
+            }
+            if inv.has("--failed") {
+                cmd["failed"] = json!(true);
+            }
+            if let Some(format) = inv.value("--format") {
+                // Only used to print the response; the daemon ignores it
+                cmd["format"] = json!(format);
+            }
+            Ok(cmd)
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "get".to_string(),
-            usage: "get <text|html|value|attr|url|title|count|box|styles> [args...]",
-        }),
-    }
-}
 
-fn parse_is(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["visible", "enabled", "checked"];
-
-    match rest.get(0).map(|s| *s) {
-        Some("visible") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is visible".to_string(),
-                usage: "is visible <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "isvisible", "selector": sel }))
+        // === Storage ===
+        ["storage", storage_type] | ["storage", storage_type, "get"] => {
+            let mut cmd = json!({ "id": id, "action": "storage_get", "type": storage_type });
+            if let Some(k) = inv.arg(0) {
+                cmd["key"] = json!(k);
+            }
+            Ok(cmd)
         }
-        Some("enabled") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is enabled".to_string(),
-                usage: "is enabled <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "isenabled", "selector": sel }))
+        ["storage", storage_type, "set"] => Ok(
+            json!({ "id": id, "action": "storage_set", "type": storage_type, "key": a(0), "value": a(1) }),
+        ),
+        ["storage", storage_type, "clear"] => {
+            Ok(json!({ "id": id, "action": "storage_clear", "type": storage_type }))
         }
-        Some("checked") => {
-            let sel = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "is checked".to_string(),
-                usage: "is checked <selector>",
-            })?;
-            Ok(json!({ "id": id, "action": "ischecked", "selector": sel }))
-        }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "is".to_string(),
-            usage: "is <visible|enabled|checked> <selector>",
-        }),
-    }
-}
-
-fn parse_find(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "role",
-        "text",
-        "label",
-        "placeholder",
-        "alt",
-        "title",
-        "testid",
-        "first",
-        "last",
-        "nth",
-    ];
-
-    let locator = rest.get(0).ok_or_else(|| ParseError::MissingArguments {
-        context: "find".to_string(),
-        usage: "find <locator> <value> [action] [text]",
-    })?;
-
-    let name_idx = rest.iter().position(|&s| s == "--name");
-    let name = name_idx.and_then(|i| rest.get(i + 1).map(|s| *s));
-    let exact = rest.iter().any(|&s| s == "--exact");
-
-    match *locator {
-        "role" | "text" | "label" | "placeholder" | "alt" | "title" | "testid" | "first"
-        | "last" => {
-            let value = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: format!("find {}", locator),
-                usage: match *locator {
-                    "role" => "find role <role> [action] [--name <name>] [--exact]",
-                    "text" => "find text <text> [action] [--exact]",
-                    "label" => "find label <label> [action] [text] [--exact]",
-                    "placeholder" => "find placeholder <text> [action] [text] [--exact]",
-                    "alt" => "find alt <text> [action] [--exact]",
-                    "title" => "find title <text> [action] [--exact]",
-                    "testid" => "find testid <id> [action] [text]",
-                    "first" => "find first <selector> [action] [text]",
-                    "last" => "find last <selector> [action] [text]",
-                    _ => "find <locator> <value> [action] [text]",
-                },
-            })?;
-            let subaction = rest.get(2).unwrap_or(&"click");
-            let fill_value = if rest.len() > 3 {
-                Some(rest[3..].join(" "))
-            } else {
-                None
-            };
 
-            match *locator {
-                "role" => {
-                    let mut cmd = json!({ "id": id, "action": "getbyrole", "role": value, "subaction": subaction, "name": name, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "text" => Ok(
-                    json!({ "id": id, "action": "getbytext", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "label" => {
-                    let mut cmd = json!({ "id": id, "action": "getbylabel", "label": value, "subaction": subaction, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "placeholder" => {
-                    let mut cmd = json!({ "id": id, "action": "getbyplaceholder", "placeholder": value, "subaction": subaction, "exact": exact });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "alt" => Ok(
-                    json!({ "id": id, "action": "getbyalttext", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "title" => Ok(
-                    json!({ "id": id, "action": "getbytitle", "text": value, "subaction": subaction, "exact": exact }),
-                ),
-                "testid" => {
-                    let mut cmd = json!({ "id": id, "action": "getbytestid", "testId": value, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "first" => {
-                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": 0, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
-                    }
-                    Ok(cmd)
-                }
-                "last" => {
-                    let mut cmd = json!({ "id": id, "action": "nth", "selector": value, "index": -1, "subaction": subaction });
-                    if let Some(v) = fill_value {
-                        cmd["value"] = json!(v);
+        // === Cookies ===
+        ["cookies"] | ["cookies", "get"] => Ok(json!({ "id": id, "action": "cookies_get" })),
+        ["cookies", "set"] => {
+            let mut cookie = json!({ "name": a(0), "value": a(1) });
+            for (flag, value) in &inv.flags {
+                let key = flag.trim_start_matches("--");
+                cookie[key] = match (*flag, value) {
+                    ("--expires", Some(_)) => {
+                        json!(number::<i64>(&inv, "--expires", "cookies set")?)
                     }
-                    Ok(cmd)
-                }
-                _ => unreachable!(),
+                    (_, Some(v)) => json!(v),
+                    (_, None) => json!(true),
+                };
+            }
+            Ok(json!({ "id": id, "action": "cookies_set", "cookies": [cookie] }))
+        }
+        ["cookies", "clear"] => Ok(json!({ "id": id, "action": "cookies_clear" })),
+
+        // === Tabs ===
+        ["tab"] => match arg_number::<i32>(&inv, 0, "tab")? {
+            Some(n) => Ok(json!({ "id": id, "action": "tab_switch", "index": n })),
+            None => Ok(json!({ "id": id, "action": "tab_list" })),
+        },
+        ["tab", "new"] => {
+            let mut cmd = json!({ "id": id, "action": "tab_new" });
+            if let Some(url) = inv.arg(0) {
+                cmd["url"] = json!(url);
             }
+            Ok(cmd)
         }
-        "nth" => {
-            let idx_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "find nth".to_string(),
-                usage: "find nth <index> <selector> [action] [text]",
-            })?;
-            let idx = idx_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "find nth".to_string(),
-                    usage: "find nth <index> <selector> [action] [text]",
-                })?;
-            let sel = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "find nth".to_string(),
-                usage: "find nth <index> <selector> [action] [text]",
-            })?;
-            let sub = rest.get(3).unwrap_or(&"click");
-            let fv = if rest.len() > 4 {
-                Some(rest[4..].join(" "))
-            } else {
-                None
-            };
-            let mut cmd = json!({ "id": id, "action": "nth", "selector": sel, "index": idx, "subaction": sub });
-            if let Some(v) = fv {
-                cmd["value"] = json!(v);
+        ["tab", "list"] => Ok(json!({ "id": id, "action": "tab_list" })),
+        ["tab", "close"] => {
+            let mut cmd = json!({ "id": id, "action": "tab_close" });
+            if let Some(index) = arg_number::<i32>(&inv, 0, "tab close")? {
+                cmd["index"] = json!(index);
             }
             Ok(cmd)
         }
-        _ => Err(ParseError::UnknownSubcommand {
-            subcommand: locator.to_string(),
-            valid_options: VALID,
-        }),
-    }
-}
 
-fn parse_mouse(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["move", "down", "up", "wheel"];
+        // === Window ===
+        ["window", "new"] => Ok(json!({ "id": id, "action": "window_new" })),
 
-    match rest.get(0).map(|s| *s) {
-        Some("move") => {
-            let x_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "mouse move".to_string(),
-                usage: "mouse move <x> <y>",
-            })?;
-            let y_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "mouse move".to_string(),
-                usage: "mouse move <x> <y>",
-            })?;
-            let x = x_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "mouse move".to_string(),
-                    usage: "mouse move <x> <y>",
-                })?;
-            let y = y_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "mouse move".to_string(),
-                    usage: "mouse move <x> <y>",
-                })?;
-            Ok(json!({ "id": id, "action": "mousemove", "x": x, "y": y }))
+        // === Frame ===
+        ["frame"] => Ok(json!({ "id": id, "action": "frame", "selector": a(0) })),
+        ["frame", "main"] => Ok(json!({ "id": id, "action": "mainframe" })),
+
+        // === Dialog ===
+        ["dialog", "accept"] => {
+            let mut cmd = json!({ "id": id, "action": "dialog", "response": "accept" });
+            if let Some(prompt_text) = inv.arg(0) {
+                cmd["promptText"] = json!(prompt_text);
+            }
+            Ok(cmd)
         }
-        Some("down") => {
-            Ok(json!({ "id": id, "action": "mousedown", "button": rest.get(1).unwrap_or(&"left") }))
+        ["dialog", "dismiss"] => Ok(json!({ "id": id, "action": "dialog", "response": "dismiss" })),
+
+        // === Debug ===
+        ["trace", "start"] => Ok(json!({ "id": id, "action": "trace_start" })),
+        ["trace", "stop"] => Ok(json!({ "id": id, "action": "trace_stop", "path": a(0) })),
+        ["har", "start"] => {
+            let mut cmd = json!({ "id": id, "action": "har_start" });
+            if let Some(content) = inv.value("--content") {
+                cmd["content"] = json!(content);
+            }
+            if let Some(filter) = inv.value("--url-filter") {
+                cmd["urlFilter"] = json!(filter);
+            }
+            Ok(cmd)
         }
-        Some("up") => {
-            Ok(json!({ "id": id, "action": "mouseup", "button": rest.get(1).unwrap_or(&"left") }))
+        ["har", "stop"] => {
+            // The daemon may run in another directory; give it an absolute path
+            let path = std::path::absolute(a(0)).map_err(|e| ParseError::InvalidValue {
+                message: format!("Invalid HAR path {}: {}", a(0), e),
+                usage: registry::usage_of("har stop"),
+            })?;
+            Ok(json!({ "id": id, "action": "har_stop", "path": path.to_string_lossy() }))
         }
-        Some("wheel") => {
-            let dy = rest
-                .get(1)
-                .and_then(|s| s.parse::<i32>().ok())
-                .unwrap_or(100);
-            let dx = rest.get(2).and_then(|s| s.parse::<i32>().ok()).unwrap_or(0);
-            Ok(json!({ "id": id, "action": "wheel", "deltaX": dx, "deltaY": dy }))
+
+        // === Recording (Playwright native video recording) ===
+        ["record", "start" | "restart"] => {
+            let mut cmd = json!({ "id": id, "action": inv.action, "path": a(0) });
+            // Optional URL parameter
+            if let Some(u) = inv.arg(1) {
+                // Add https:// prefix if needed
+                let url_str = if u.starts_with("http") {
+                    u.to_string()
+                } else {
+                    format!("https://{}", u)
+                };
+                cmd["url"] = json!(url_str);
+            }
+            Ok(cmd)
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "mouse".to_string(),
-            usage: "mouse <move|down|up|wheel> [args...]",
+        ["record", "stop"] => Ok(json!({ "id": id, "action": "recording_stop" })),
+        ["console" | "errors"] => {
+            Ok(json!({ "id": id, "action": inv.action, "clear": inv.has("--clear") }))
+        }
+
+        // === State ===
+        ["state", "save"] => Ok(json!({ "id": id, "action": "state_save", "path": a(0) })),
+        ["state", "load"] => Ok(json!({ "id": id, "action": "state_load", "path": a(0) })),
+
+        // Registered, but handled by the CLI rather than the daemon
+        _ => Err(ParseError::UnknownCommand {
+            command: inv.path.join(" "),
         }),
     }
 }
 
-fn parse_set(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &[
-        "viewport",
-        "device",
-        "geo",
-        "geolocation",
-        "offline",
-        "headers",
-        "credentials",
-        "auth",
-        "media",
-    ];
-
-    match rest.get(0).map(|s| *s) {
-        Some("viewport") => {
-            let w_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set viewport".to_string(),
-                usage: "set viewport <width> <height>",
-            })?;
-            let h_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set viewport".to_string(),
-                usage: "set viewport <width> <height>",
-            })?;
-            let w = w_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set viewport".to_string(),
-                    usage: "set viewport <width> <height>",
-                })?;
-            let h = h_str
-                .parse::<i32>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set viewport".to_string(),
-                    usage: "set viewport <width> <height>",
-                })?;
-            Ok(json!({ "id": id, "action": "viewport", "width": w, "height": h }))
-        }
-        Some("device") => {
-            let dev = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set device".to_string(),
-                usage: "set device <name>",
-            })?;
-            Ok(json!({ "id": id, "action": "device", "device": dev }))
-        }
-        Some("geo") | Some("geolocation") => {
-            let lat_str = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set geo".to_string(),
-                usage: "set geo <latitude> <longitude>",
-            })?;
-            let lng_str = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set geo".to_string(),
-                usage: "set geo <latitude> <longitude>",
-            })?;
-            let lat = lat_str
-                .parse::<f64>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set geo".to_string(),
-                    usage: "set geo <latitude> <longitude>",
-                })?;
-            let lng = lng_str
-                .parse::<f64>()
-                .map_err(|_| ParseError::MissingArguments {
-                    context: "set geo".to_string(),
-                    usage: "set geo <latitude> <longitude>",
-                })?;
-            Ok(json!({ "id": id, "action": "geolocation", "latitude": lat, "longitude": lng }))
+fn parse_find(locator: &str, inv: &Invocation, id: &str) -> Result<Value, ParseError> {
+    let name = inv.value("--name");
+    let exact = inv.has("--exact");
+
+    // nth takes an index before the selector
+    let (index, value, rest) = match locator {
+        "nth" => (
+            arg_number::<i32>(inv, 0, "find nth")?,
+            inv.arg(1).unwrap_or_default(),
+            inv.args.get(2..).unwrap_or_default(),
+        ),
+        _ => (
+            None,
+            inv.arg(0).unwrap_or_default(),
+            inv.args.get(1..).unwrap_or_default(),
+        ),
+    };
+    let subaction = rest.first().map_or("click", |s| s.as_str());
+    let fill_value = rest.get(1..).filter(|v| !v.is_empty()).map(|v| v.join(" "));
+
+    let mut cmd = match locator {
+        "role" => {
+            json!({ "id": id, "action": "getbyrole", "role": value, "subaction": subaction, "name": name, "exact": exact })
         }
-        Some("offline") => {
-            let off = rest
-                .get(1)
-                .map(|s| *s != "off" && *s != "false")
-                .unwrap_or(true);
-            Ok(json!({ "id": id, "action": "offline", "offline": off }))
+        "text" => {
+            json!({ "id": id, "action": "getbytext", "text": value, "subaction": subaction, "exact": exact })
         }
-        Some("headers") => {
-            let headers_json = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set headers".to_string(),
-                usage: "set headers <json>",
-            })?;
-            // Parse the JSON string into an object
-            let headers: serde_json::Value =
-                serde_json::from_str(headers_json).map_err(|_| ParseError::MissingArguments {
-                    context: "set headers".to_string(),
-                    usage: "set headers <json> (must be valid JSON object)",
-                })?;
-            Ok(json!({ "id": id, "action": "headers", "headers": headers }))
+        "label" => {
+            json!({ "id": id, "action": "getbylabel", "label": value, "subaction": subaction, "exact": exact })
         }
-        Some("credentials") | Some("auth") => {
-            let user = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            let pass = rest.get(2).ok_or_else(|| ParseError::MissingArguments {
-                context: "set credentials".to_string(),
-                usage: "set credentials <username> <password>",
-            })?;
-            Ok(json!({ "id": id, "action": "credentials", "username": user, "password": pass }))
+        "placeholder" => {
+            json!({ "id": id, "action": "getbyplaceholder", "placeholder": value, "subaction": subaction, "exact": exact })
         }
-        Some("media") => {
-            let color = if rest.iter().any(|&s| s == "dark") {
-                "dark"
-            } else if rest.iter().any(|&s| s == "light") {
-                "light"
-            } else {
-                "no-preference"
-            };
-            let reduced = if rest.iter().any(|&s| s == "reduced-motion") {
-                "reduce"
-            } else {
-                "no-preference"
-            };
-            Ok(
-                json!({ "id": id, "action": "emulatemedia", "colorScheme": color, "reducedMotion": reduced }),
-            )
+        "alt" => {
+            json!({ "id": id, "action": "getbyalttext", "text": value, "subaction": subaction, "exact": exact })
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "set".to_string(),
-            usage: "set <viewport|device|geo|offline|headers|credentials|media> [args...]",
-        }),
-    }
-}
-
-fn parse_network(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["route", "unroute", "requests"];
-
-    match rest.get(0).map(|s| *s) {
-        Some("route") => {
-            let url = rest.get(1).ok_or_else(|| ParseError::MissingArguments {
-                context: "network route".to_string(),
-                usage: "network route <url> [--abort|--body <json>]",
-            })?;
-            let abort = rest.iter().any(|&s| s == "--abort");
-            let body_idx = rest.iter().position(|&s| s == "--body");
-            let body = body_idx.and_then(|i| rest.get(i + 1).map(|s| *s));
-            Ok(json!({ "id": id, "action": "route", "url": url, "abort": abort, "body": body }))
+        "title" => {
+            json!({ "id": id, "action": "getbytitle", "text": value, "subaction": subaction, "exact": exact })
         }
-        Some("unroute") => {
-            let mut cmd = json!({ "id": id, "action": "unroute" });
-            if let Some(url) = rest.get(1) {
-                cmd["url"] = json!(url);
-            }
-            Ok(cmd)
+        "testid" => {
+            json!({ "id": id, "action": "getbytestid", "testId": value, "subaction": subaction })
         }
-        Some("requests") => {
-            let clear = rest.iter().any(|&s| s == "--clear");
-            let filter_idx = rest.iter().position(|&s| s == "--filter");
-            let filter = filter_idx.and_then(|i| rest.get(i + 1).map(|s| *s));
-            let mut cmd = json!({ "id": id, "action": "requests", "clear": clear });
-            if let Some(f) = filter {
-                cmd["filter"] = json!(f);
-            }
-            Ok(cmd)
+        _ => {
+            let index = match locator {
+                "first" => 0,
+                "last" => -1,
+                _ => index.unwrap_or_default(),
+            };
+            json!({ "id": id, "action": "nth", "selector": value, "index": index, "subaction": subaction })
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "network".to_string(),
-            usage: "network <route|unroute|requests> [args...]",
-        }),
-    }
-}
-
-fn parse_storage(rest: &[&str], id: &str) -> Result<Value, ParseError> {
-    const VALID: &[&str] = &["local", "session"];
-
-    match rest.get(0).map(|s| *s) {
-        Some("local") | Some("session") => {
-            let storage_type = rest.get(0).unwrap();
-            let op = rest.get(1).unwrap_or(&"get");
-            let key = rest.get(2);
-            let value = rest.get(3);
-            match *op {
-                "set" => {
-                    let k = key.ok_or_else(|| ParseError::MissingArguments {
-                        context: format!("storage {} set", storage_type),
-                        usage: "storage <local|session> set <key> <value>",
-                    })?;
-                    let v = value.ok_or_else(|| ParseError::MissingArguments {
-                        context: format!("storage {} set", storage_type),
-                        usage: "storage <local|session> set <key> <value>",
-                    })?;
-                    Ok(
-                        json!({ "id": id, "action": "storage_set", "type": storage_type, "key": k, "value": v }),
-                    )
-                }
-                "clear" => Ok(json!({ "id": id, "action": "storage_clear", "type": storage_type })),
-                _ => {
-                    let mut cmd =
-                        json!({ "id": id, "action": "storage_get", "type": storage_type });
-                    if let Some(k) = key {
-                        cmd.as_object_mut()
-                            .unwrap()
-                            .insert("key".to_string(), json!(k));
-                    }
-                    Ok(cmd)
-                }
-            }
+    };
+    // Only locators that can target inputs take a fill value
+    if !matches!(locator, "text" | "alt" | "title") {
+        if let Some(v) = fill_value {
+            cmd["value"] = json!(v);
         }
-        Some(sub) => Err(ParseError::UnknownSubcommand {
-            subcommand: sub.to_string(),
-            valid_options: VALID,
-        }),
-        None => Err(ParseError::MissingArguments {
-            context: "storage".to_string(),
-            usage: "storage <local|session> [get|set|clear] [key] [value]",
-        }),
     }
+    Ok(cmd)
 }
 
 #[cfg(test)]
@@ -1324,6 +955,7 @@ mod tests {
     fn default_flags() -> Flags {
         Flags {
             session: "test".to_string(),
+            sessions: None,
             json: false,
             full: false,
             headed: false,
@@ -1336,10 +968,22 @@ mod tests {
             state: None,
             proxy: None,
             proxy_bypass: None,
+            proxy_pac: None,
+            proxy_credentials_file: None,
+            proxy_pool: None,
+            proxy_pool_strategy: None,
             args: None,
             user_agent: None,
             provider: None,
             ignore_https_errors: false,
+            auto_recover: false,
+            env: None,
+            base_url: None,
+            credentials: None,
+            viewport: None,
+            locale: None,
+            strict: true,
+            explicit: Vec::new(),
         }
     }
 
@@ -1349,6 +993,41 @@ mod tests {
 
     // === Cookies Tests ===
 
+    #[test]
+    fn test_navigate_resolves_path_against_base_url() {
+        let mut flags = default_flags();
+        flags.base_url = Some("https://staging.example.com/app/".to_string());
+        let cmd = parse_command(&args("open /login"), &flags).unwrap();
+        assert_eq!(cmd["url"], "https://staging.example.com/login");
+
+        // Hosts and full URLs are unaffected
+        let cmd = parse_command(&args("open example.com"), &flags).unwrap();
+        assert_eq!(cmd["url"], "https://example.com");
+        let cmd = parse_command(&args("open http://localhost:3000/x"), &flags).unwrap();
+        assert_eq!(cmd["url"], "http://localhost:3000/x");
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(
+            resolve_path("http://localhost:3000", "/a?b=1"),
+            "http://localhost:3000/a?b=1"
+        );
+        assert_eq!(
+            resolve_path("https://x.test?q=1", "/login"),
+            "https://x.test/login"
+        );
+    }
+
+    #[test]
+    fn test_gen_id_unique() {
+        let ids: std::collections::HashSet<String> = (0..1000).map(|_| gen_id()).collect();
+        assert_eq!(ids.len(), 1000);
+        assert!(ids
+            .iter()
+            .all(|id| id.starts_with(&format!("r{}-", std::process::id()))));
+    }
+
     #[test]
     fn test_cookies_get() {
         let cmd = parse_command(&args("cookies"), &default_flags()).unwrap();
@@ -1510,6 +1189,31 @@ mod tests {
 
     // === Storage Tests ===
 
+    #[test]
+    fn test_integer_overflow_is_invalid_value() {
+        for line in [
+            "scroll down 99999999999",
+            "mouse move 1 3000000000",
+            "mouse wheel 3000000000",
+            "tab 5000000000",
+            "tab close 5000000000",
+            "set viewport 5000000000 1",
+            "snapshot -d 9999999999",
+            "find nth 5000000000 .item click",
+            "wait --download --timeout -1",
+        ] {
+            let err = parse_command(&args(line), &default_flags()).unwrap_err();
+            assert!(
+                matches!(err, ParseError::InvalidValue { .. }),
+                "{}: {:?}",
+                line,
+                err
+            );
+        }
+        let err = parse_command(&args("tab 5000000000"), &default_flags()).unwrap_err();
+        assert!(err.format().contains("Invalid value for n: 5000000000"));
+    }
+
     #[test]
     fn test_storage_local_get() {
         let cmd = parse_command(&args("storage local"), &default_flags()).unwrap();
@@ -1526,6 +1230,14 @@ mod tests {
         assert_eq!(cmd["key"], "mykey");
     }
 
+    #[test]
+    fn test_storage_local_key_shorthand() {
+        let cmd = parse_command(&args("storage local mykey"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "storage_get");
+        assert_eq!(cmd["type"], "local");
+        assert_eq!(cmd["key"], "mykey");
+    }
+
     #[test]
     fn test_storage_session_get() {
         let cmd = parse_command(&args("storage session"), &default_flags()).unwrap();
@@ -1634,6 +1346,42 @@ mod tests {
 
     // === Set Headers Tests ===
 
+    #[test]
+    fn test_set_network() {
+        let cmd = parse_command(&args("set network slow-3g"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "network_conditions");
+        assert_eq!(cmd["profile"], "slow-3g");
+
+        let cmd = parse_command(
+            &args("set network custom --down 1000 --up 500 --latency 300"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["downloadKbps"], 1000);
+        assert_eq!(cmd["uploadKbps"], 500);
+        assert_eq!(cmd["latency"], 300);
+
+        for bad in [
+            "set network custom",
+            "set network fast-3g --down 1000",
+            "set network 2g",
+        ] {
+            assert!(
+                parse_command(&args(bad), &default_flags()).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_set_cpu() {
+        let cmd = parse_command(&args("set cpu 4"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "cpu_throttle");
+        assert_eq!(cmd["rate"], 4.0);
+        assert!(parse_command(&args("set cpu 0.5"), &default_flags()).is_err());
+    }
+
     #[test]
     fn test_set_headers_parses_json() {
         let input: Vec<String> = vec![
@@ -1736,6 +1484,23 @@ mod tests {
         assert_eq!(cmd["action"], "mainframe");
     }
 
+    // === Dialogs ===
+
+    #[test]
+    fn test_dialog_accept_with_prompt_text() {
+        let cmd = parse_command(&args("dialog accept hello"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog");
+        assert_eq!(cmd["response"], "accept");
+        assert_eq!(cmd["promptText"], "hello");
+    }
+
+    #[test]
+    fn test_dialog_dismiss() {
+        let cmd = parse_command(&args("dialog dismiss"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "dialog");
+        assert_eq!(cmd["response"], "dismiss");
+    }
+
     // === Tabs ===
 
     #[test]
@@ -2035,6 +1800,314 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_suggestions() {
+        let err = parse_command(&args("clik @e1"), &default_flags()).unwrap_err();
+        assert_eq!(err.suggestions(), vec!["click"]);
+        assert!(err.format().contains("Did you mean \"click\"?"));
+
+        let err = parse_command(&args("get txt @e1"), &default_flags()).unwrap_err();
+        assert_eq!(err.suggestions(), vec!["text"]);
+
+        let err = parse_command(&args("find text Foo --exat"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::UnknownFlag { .. }));
+        assert_eq!(err.suggestions(), vec!["--exact"]);
+        assert_eq!(err.message(), "Unknown flag: --exat");
+        assert_eq!(
+            err.format(),
+            "Unknown flag: --exat\nDid you mean \"--exact\"?"
+        );
+
+        // A misspelled leading global flag isn't an unknown command
+        let err = parse_command(&args("--sesion x open a"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::UnknownFlag { .. }));
+        assert_eq!(err.suggestions(), vec!["--session"]);
+
+        let err = parse_command(&args("eval --x=1"), &default_flags()).unwrap_err();
+        assert!(err.suggestions().is_empty());
+
+        let err = parse_command(&args("qwertyuiop"), &default_flags()).unwrap_err();
+        assert!(err.suggestions().is_empty());
+        assert_eq!(err.format(), "Unknown command: qwertyuiop");
+    }
+
+    #[test]
+    fn test_strict_flags() {
+        // Flags are recognized wherever they appear
+        let after = parse_command(&args("find text Foo --exact"), &default_flags()).unwrap();
+        let before = parse_command(&args("find text --exact Foo"), &default_flags()).unwrap();
+        assert_eq!(after["text"], "Foo");
+        assert_eq!(before["text"], "Foo");
+        assert_eq!(after["exact"], true);
+        assert_eq!(before["exact"], true);
+
+        let err = parse_command(&args("snapshot --fulll"), &default_flags()).unwrap_err();
+        assert!(matches!(err, ParseError::UnknownFlag { .. }));
+        // Negative numbers aren't flags
+        assert!(parse_command(&args("scroll up -100"), &default_flags()).is_ok());
+
+        let lenient = Flags {
+            strict: false,
+            ..default_flags()
+        };
+        let cmd = parse_command(&args("fill #x -5 --bogus"), &lenient).unwrap();
+        assert_eq!(cmd["value"], "-5 --bogus");
+    }
+
+    #[test]
+    fn test_terminator_values_starting_with_dash() {
+        let cmd = parse_command(&args("fill #x -- -5"), &default_flags()).unwrap();
+        assert_eq!(cmd["value"], "-5");
+        let cmd = parse_command(&args("type #x -- --not-a-flag"), &default_flags()).unwrap();
+        assert_eq!(cmd["text"], "--not-a-flag");
+    }
+
+    #[test]
+    fn test_har() {
+        let cmd = parse_command(&args("har start"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "har_start");
+        assert!(cmd.get("content").is_none());
+
+        let cmd = parse_command(
+            &args("har start --content attach --url-filter **/api/**"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["content"], "attach");
+        assert_eq!(cmd["urlFilter"], "**/api/**");
+
+        assert!(parse_command(&args("har start --content inline"), &default_flags()).is_err());
+        assert!(parse_command(&args("har stop"), &default_flags()).is_err());
+
+        let cmd = parse_command(&args("har stop ./bug.har"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "har_stop");
+        let expected = std::env::current_dir().unwrap().join("bug.har");
+        assert_eq!(cmd["path"], expected.to_string_lossy().as_ref());
+    }
+
+    #[test]
+    fn test_network_route() {
+        let cmd = parse_command(&args("network route **/api/* --abort"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "route");
+        assert_eq!(cmd["abort"], true);
+        assert!(cmd.get("response").is_none());
+
+        let cmd = parse_command(
+            &args(
+                "network route **/api/* --status 503 --header Retry-After:30 --header x-a:1 \
+                 --body {\"down\":true} --delay 2000 --times 2 --method post",
+            ),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["url"], "**/api/*");
+        assert_eq!(cmd["method"], "POST");
+        assert_eq!(cmd["delay"], 2000);
+        assert_eq!(cmd["times"], 2);
+        assert_eq!(cmd["response"]["status"], 503);
+        assert_eq!(cmd["response"]["headers"]["Retry-After"], "30");
+        assert_eq!(cmd["response"]["headers"]["x-a"], "1");
+        assert_eq!(cmd["response"]["body"], r#"{"down":true}"#);
+        assert_eq!(cmd["response"]["contentType"], "application/json");
+
+        for bad in [
+            "network route a --header nocolon",
+            "network route a --abort --status 500",
+            "network route a --status 99999",
+            "network route a --body-file /nonexistent/body.json",
+        ] {
+            assert!(
+                matches!(
+                    parse_command(&args(bad), &default_flags()),
+                    Err(ParseError::InvalidValue { .. })
+                ),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_network_requests_filters() {
+        let cmd = parse_command(
+            &args("network requests --method post --type fetch --status 4xx,500-599 --failed --format csv"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "requests");
+        assert_eq!(cmd["method"], "POST");
+        assert_eq!(cmd["resourceType"], "fetch");
+        assert_eq!(cmd["status"], "4xx,500-599");
+        assert_eq!(cmd["failed"], true);
+        assert_eq!(cmd["format"], "csv");
+
+        let cmd = parse_command(&args("network requests --since 30s"), &default_flags()).unwrap();
+        let since = cmd["since"].as_u64().unwrap();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        assert!(since <= now - 30_000 && since > now - 60_000);
+
+        for bad in [
+            "network requests --status 4x",
+            "network requests --status 9xx",
+            "network requests --since soon",
+            "network requests --type iframe",
+        ] {
+            assert!(
+                parse_command(&args(bad), &default_flags()).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(
+            parse_duration("500ms"),
+            Some(std::time::Duration::from_millis(500))
+        );
+        assert_eq!(
+            parse_duration("30s"),
+            Some(std::time::Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_duration("5m"),
+            Some(std::time::Duration::from_secs(300))
+        );
+        assert_eq!(parse_duration("2"), Some(std::time::Duration::from_secs(2)));
+        assert_eq!(
+            parse_duration("1h"),
+            Some(std::time::Duration::from_secs(3600))
+        );
+        assert_eq!(parse_duration("m"), None);
+        assert_eq!(parse_duration("3d"), None);
+    }
+
+    #[test]
+    fn test_fetch() {
+        let cmd = parse_command(&args("fetch example.com/api/me"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "fetch");
+        assert_eq!(cmd["url"], "https://example.com/api/me");
+        assert!(cmd.get("method").is_none());
+
+        let mut flags = default_flags();
+        flags.headers = Some(r#"{"Authorization": "Bearer token", "X-A": "1"}"#.to_string());
+        flags.explicit = vec!["--headers"];
+        let cmd = parse_command(
+            &args(r#"fetch /api/orders -X post -d {"qty":2} -H X-A:2 --out order.json"#),
+            &flags,
+        )
+        .unwrap();
+        assert_eq!(cmd["url"], "/api/orders");
+        assert_eq!(cmd["method"], "POST");
+        assert_eq!(cmd["data"], r#"{"qty":2}"#);
+        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+        assert_eq!(cmd["headers"]["X-A"], "2");
+        assert_eq!(cmd["headers"]["Content-Type"], "application/json");
+        assert!(std::path::Path::new(cmd["path"].as_str().unwrap()).is_absolute());
+
+        let mut flags = default_flags();
+        flags.base_url = Some("https://app.example.com".to_string());
+        let cmd = parse_command(&args("fetch /api/me"), &flags).unwrap();
+        assert_eq!(cmd["url"], "https://app.example.com/api/me");
+
+        // Profile headers go to the base_url's origin only
+        flags.headers = Some(r#"{"Authorization": "Bearer token"}"#.to_string());
+        let cmd = parse_command(&args("fetch /api/me"), &flags).unwrap();
+        assert_eq!(cmd["headers"]["Authorization"], "Bearer token");
+        let cmd = parse_command(&args("fetch https://evil.example.net/x"), &flags).unwrap();
+        assert!(cmd.get("headers").is_none());
+        let cmd = parse_command(&args("fetch https://app.example.com.evil.net/"), &flags).unwrap();
+        assert!(cmd.get("headers").is_none());
+
+        assert!(parse_command(
+            &args("fetch a.com -d x --data-file Cargo.toml"),
+            &default_flags()
+        )
+        .is_err());
+        assert!(parse_command(
+            &args("fetch a.com --data-file /nonexistent"),
+            &default_flags()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_network_body() {
+        let cmd = parse_command(&args("network body 12"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "network_body");
+        assert_eq!(cmd["index"], 12);
+        assert!(cmd.get("url").is_none());
+
+        let cmd = parse_command(
+            &args("network body **/api/users --out users.json"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["url"], "**/api/users");
+        let out = std::path::Path::new(cmd["path"].as_str().unwrap());
+        assert!(out.is_absolute() && out.ends_with("users.json"));
+
+        let cmd = parse_command(
+            &args("network wait-response /api/search --timeout 5000"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "responsebody");
+        assert_eq!(cmd["url"], "/api/search");
+        assert_eq!(cmd["timeout"], 5000);
+
+        // A number is a URL fragment when waiting, not a log index
+        let cmd = parse_command(&args("network wait-response 404"), &default_flags()).unwrap();
+        assert_eq!(cmd["url"], "404");
+    }
+
+    #[test]
+    fn test_network_block() {
+        let cmd = parse_command(
+            &args("network block --types image,font --types media --domains @ads,example.com"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "block");
+        assert_eq!(cmd["types"], json!(["image", "font", "media"]));
+        assert_eq!(cmd["domains"], json!(["@ads", "example.com"]));
+
+        let cmd = parse_command(&args("network unblock"), &default_flags()).unwrap();
+        assert_eq!(cmd["action"], "unblock");
+
+        let cmd = parse_command(&args("network block"), &default_flags()).unwrap();
+        assert!(cmd.get("types").is_none() && cmd.get("domains").is_none());
+        assert!(matches!(
+            parse_command(&args("network block --types images"), &default_flags()),
+            Err(ParseError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_network_replay() {
+        let cmd = parse_command(
+            &args("network replay login.har --not-found fallback --url **/api/**"),
+            &default_flags(),
+        )
+        .unwrap();
+        assert_eq!(cmd["action"], "har_replay");
+        let path = std::path::Path::new(cmd["path"].as_str().unwrap());
+        assert!(path.is_absolute());
+        assert!(path.ends_with("login.har"));
+        assert_eq!(cmd["notFound"], "fallback");
+        assert_eq!(cmd["url"], "**/api/**");
+
+        assert!(parse_command(
+            &args("network replay a.har --not-found skip"),
+            &default_flags()
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_empty_args() {
         let result = parse_command(&[], &default_flags());
@@ -0,0 +1,559 @@
+//! Shell completion scripts.
+//!
+//! `completions <bash|zsh|fish>` prints a script generated from the command
+//! registry and the global flag lists in `flags`. Dynamic values (session
+//! names, config profiles, device names) are fetched at completion time from
+//! the hidden `__complete <kind>` command.
+
+use std::process::exit;
+
+use crate::commands::ParseError;
+use crate::config::Config;
+use crate::connection::list_sessions;
+use crate::flags::{Flags, GLOBAL_FLAGS, GLOBAL_FLAGS_WITH_VALUE};
+use crate::output::print_parse_error;
+use crate::registry::{self, ArgType, Command, Flag};
+
+/// Flags whose value is a file or directory
+const PATH_FLAGS: &[&str] = &["--executable-path", "--extension", "--profile", "--state"];
+
+/// Commonly used Playwright device descriptors for `set device`
+const DEVICES: &[&str] = &[
+    "Desktop Chrome",
+    "Desktop Edge",
+    "Desktop Firefox",
+    "Desktop Safari",
+    "Galaxy S8",
+    "Galaxy S9+",
+    "Galaxy Tab S4",
+    "iPad (gen 7)",
+    "iPad Mini",
+    "iPad Pro 11",
+    "iPhone 12",
+    "iPhone 12 Pro",
+    "iPhone 12 Pro Max",
+    "iPhone 13",
+    "iPhone 13 Mini",
+    "iPhone 13 Pro",
+    "iPhone 13 Pro Max",
+    "iPhone 14",
+    "iPhone 14 Plus",
+    "iPhone 14 Pro",
+    "iPhone 14 Pro Max",
+    "iPhone 15",
+    "iPhone 15 Plus",
+    "iPhone 15 Pro",
+    "iPhone 15 Pro Max",
+    "iPhone SE",
+    "Pixel 5",
+    "Pixel 7",
+];
+
+pub fn run_completions(args: &[String], flags: &Flags) {
+    const VALID: &[&str] = &["bash", "zsh", "fish"];
+
+    let script = match args.get(1).map(|s| s.as_str()) {
+        Some("bash") => bash(),
+        Some("zsh") => zsh(),
+        Some("fish") => fish(),
+        Some(shell) => {
+            print_parse_error(
+                &ParseError::UnknownSubcommand {
+                    subcommand: shell.to_string(),
+                    valid_options: VALID.to_vec(),
+                },
+                flags.json,
+            );
+            exit(1);
+        }
+        None => {
+            print_parse_error(
+                &ParseError::MissingArguments {
+                    context: "completions".to_string(),
+                    usage: "completions <bash|zsh|fish>".to_string(),
+                },
+                flags.json,
+            );
+            exit(1);
+        }
+    };
+    print!("{}", script);
+}
+
+/// `__complete <kind>`: dynamic values for completion scripts, one per line
+pub fn run_complete_values(args: &[String], config: &Config) {
+    let values: Vec<String> = match args.get(1).map(|s| s.as_str()) {
+        Some("sessions") => list_sessions(),
+        Some("envs") => config.profile_names(),
+        Some("devices") => DEVICES.iter().map(|d| d.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    for value in values {
+        println!("{}", value);
+    }
+}
+
+fn names(c: &Command) -> impl Iterator<Item = &'static str> {
+    std::iter::once(c.name).chain(c.aliases.iter().copied())
+}
+
+fn command_names() -> Vec<&'static str> {
+    registry::COMMANDS.iter().flat_map(names).collect()
+}
+
+fn global_flags() -> Vec<&'static str> {
+    GLOBAL_FLAGS
+        .iter()
+        .chain(GLOBAL_FLAGS_WITH_VALUE.iter())
+        .copied()
+        .chain(["--help", "--version"])
+        .collect()
+}
+
+/// `a|b|c` case pattern for a command and its aliases
+fn case_pattern(c: &Command) -> String {
+    names(c).collect::<Vec<_>>().join("|")
+}
+
+/// Flags of a command and all of its subcommands, each once
+fn all_flags(c: &'static Command) -> Vec<&'static Flag> {
+    let mut flags: Vec<&'static Flag> = Vec::new();
+    for flag in c
+        .flags
+        .iter()
+        .chain(c.subcommands.iter().flat_map(all_flags))
+    {
+        if !flags.iter().any(|f| f.long == flag.long) {
+            flags.push(flag);
+        }
+    }
+    flags
+}
+
+fn registry_flags() -> Vec<&'static Flag> {
+    let mut flags: Vec<&'static Flag> = Vec::new();
+    for flag in registry::COMMANDS.iter().flat_map(all_flags) {
+        if !flags.iter().any(|f| f.long == flag.long) {
+            flags.push(flag);
+        }
+    }
+    flags
+}
+
+fn value_flags_pattern() -> String {
+    GLOBAL_FLAGS_WITH_VALUE
+        .iter()
+        .copied()
+        .chain(
+            registry_flags()
+                .into_iter()
+                .filter(|f| f.value.is_some())
+                .flat_map(|f| std::iter::once(f.long).chain(f.short)),
+        )
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Words completing the argument after `c`: its subcommands, or the choices
+/// of its first argument
+fn next_words(c: &Command) -> Vec<&'static str> {
+    let mut words: Vec<&'static str> = c.subcommands.iter().flat_map(names).collect();
+    if let Some(ArgType::Choice(options)) = c.args.first().map(|a| a.ty) {
+        words.extend(options.iter());
+    }
+    words
+}
+
+/// `__complete` kind for the first argument of `c`
+fn dynamic_kind(c: &Command) -> Option<&'static str> {
+    match c.args.first().map(|a| a.ty) {
+        Some(ArgType::Session) => Some("sessions"),
+        Some(ArgType::Device) => Some("devices"),
+        _ => None,
+    }
+}
+
+/// `case` arms (bash and zsh share the syntax) completing `words` for each
+/// command; `emit` renders the completion of a space-separated word list
+fn case_arms(items: &[(String, String)], indent: &str, emit: impl Fn(&str) -> String) -> String {
+    items
+        .iter()
+        .map(|(pattern, words)| format!("{}{}) {} ;;\n", indent, pattern, emit(words)))
+        .collect()
+}
+
+fn subcommand_items() -> Vec<(String, String)> {
+    registry::COMMANDS
+        .iter()
+        .map(|c| (case_pattern(c), next_words(c).join(" ")))
+        .filter(|(_, words)| !words.is_empty())
+        .collect()
+}
+
+fn flag_items() -> Vec<(String, String)> {
+    registry::COMMANDS
+        .iter()
+        .map(|c| {
+            let flags: Vec<&str> = all_flags(c).iter().map(|f| f.long).collect();
+            (case_pattern(c), flags.join(" "))
+        })
+        .filter(|(_, flags)| !flags.is_empty())
+        .collect()
+}
+
+/// `"cmd sub"` patterns (every alias spelling) with a `(cmd, sub)` value
+fn sub_items<T>(value: impl Fn(&Command) -> Option<T>) -> Vec<(String, T)> {
+    let mut items = Vec::new();
+    for c in registry::COMMANDS {
+        for sub in c.subcommands {
+            if let Some(v) = value(sub) {
+                let patterns: Vec<String> = names(c)
+                    .flat_map(|cn| names(sub).map(move |sn| format!("\"{} {}\"", cn, sn)))
+                    .collect();
+                items.push((patterns.join("|"), v));
+            }
+        }
+    }
+    items
+}
+
+fn nested_items() -> Vec<(String, String)> {
+    sub_items(|sub| Some(next_words(sub).join(" ")).filter(|w| !w.is_empty()))
+}
+
+fn dynamic_items() -> Vec<(String, &'static str)> {
+    sub_items(dynamic_kind)
+}
+
+/// Flag values with a fixed set of choices, e.g. `--load`
+fn choice_flag_items() -> Vec<(String, String)> {
+    registry_flags()
+        .into_iter()
+        .filter_map(|f| match f.value.map(|v| v.ty) {
+            Some(ArgType::Choice(options)) => {
+                let pattern = std::iter::once(f.long).chain(f.short).collect::<Vec<_>>();
+                Some((pattern.join("|"), options.join(" ")))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// `case "$cmd $sub"` arms for arguments completed by `__complete`
+fn dynamic_arms(indent: &str, emit: impl Fn(&str) -> String) -> String {
+    dynamic_items()
+        .iter()
+        .map(|(pattern, kind)| format!("{}{}) {} ;;\n", indent, pattern, emit(kind)))
+        .collect()
+}
+
+const BASH_TEMPLATE: &str = r#"# bash completion for agent-browser
+# Load with: source <(agent-browser completions bash)
+
+_agent_browser_values() {
+    local word
+    COMPREPLY=()
+    while IFS= read -r word; do
+        [[ $word == "$cur"* ]] && COMPREPLY+=("$(printf '%q' "$word")")
+    done < <(agent-browser __complete "$1" 2>/dev/null)
+}
+
+_agent_browser() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    local cmd="" sub="" word i
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        word="${COMP_WORDS[i]}"
+        case "$word" in
+            @VALUE_FLAGS@) ((i++)) ;;
+            -*) ;;
+            *)
+                if [[ -z $cmd ]]; then
+                    cmd="$word"
+                elif [[ -z $sub ]]; then
+                    sub="$word"
+                fi
+                ;;
+        esac
+    done
+
+    case "$prev" in
+        --session|--sessions) _agent_browser_values sessions; return ;;
+        --env) _agent_browser_values envs; return ;;
+@CHOICE_ARMS@        @VALUE_FLAGS@) COMPREPLY=($(compgen -f -- "$cur")); return ;;
+    esac
+
+    case "$cmd $sub" in
+@DYNAMIC_ARMS@    esac
+
+    if [[ $cur == -* ]]; then
+        local flags="@GLOBAL_FLAGS@"
+        case "$cmd" in
+@FLAG_ARMS@        esac
+        COMPREPLY=($(compgen -W "$flags" -- "$cur"))
+    elif [[ -z $cmd ]]; then
+        COMPREPLY=($(compgen -W "@COMMANDS@" -- "$cur"))
+    elif [[ -z $sub ]]; then
+        case "$cmd" in
+@SUB_ARMS@        esac
+    else
+        case "$cmd $sub" in
+@NESTED_ARMS@        esac
+    fi
+}
+
+complete -F _agent_browser agent-browser
+"#;
+
+fn bash() -> String {
+    let compgen = |words: &str| format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", words);
+    let values = |kind: &str| format!("_agent_browser_values {}; return", kind);
+    let indent = " ".repeat(12);
+    BASH_TEMPLATE
+        .replace("@VALUE_FLAGS@", &value_flags_pattern())
+        .replace(
+            "@CHOICE_ARMS@",
+            &case_arms(&choice_flag_items(), "        ", |w| {
+                format!("{}; return", compgen(w))
+            }),
+        )
+        .replace("@DYNAMIC_ARMS@", &dynamic_arms("        ", values))
+        .replace("@GLOBAL_FLAGS@", &global_flags().join(" "))
+        .replace("@COMMANDS@", &command_names().join(" "))
+        .replace(
+            "@FLAG_ARMS@",
+            &case_arms(&flag_items(), &indent, |w| format!("flags+=\" {}\"", w)),
+        )
+        .replace(
+            "@SUB_ARMS@",
+            &case_arms(&subcommand_items(), &indent, compgen),
+        )
+        .replace(
+            "@NESTED_ARMS@",
+            &case_arms(&nested_items(), &indent, compgen),
+        )
+}
+
+const ZSH_TEMPLATE: &str = r#"#compdef agent-browser
+# zsh completion for agent-browser
+# Install with: agent-browser completions zsh > "${fpath[1]}/_agent-browser"
+
+_agent_browser_values() {
+    local -a values
+    values=("${(@f)$(agent-browser __complete $1 2>/dev/null)}")
+    compadd -a values
+}
+
+_agent-browser() {
+    local cur="${words[CURRENT]}" prev="${words[CURRENT-1]}"
+    local cmd="" sub="" word i
+    for ((i = 2; i < CURRENT; i++)); do
+        word="${words[i]}"
+        case "$word" in
+            @VALUE_FLAGS@) ((i++)) ;;
+            -*) ;;
+            *)
+                if [[ -z $cmd ]]; then
+                    cmd="$word"
+                elif [[ -z $sub ]]; then
+                    sub="$word"
+                fi
+                ;;
+        esac
+    done
+
+    case "$prev" in
+        --session|--sessions) _agent_browser_values sessions; return ;;
+        --env) _agent_browser_values envs; return ;;
+@CHOICE_ARMS@        @VALUE_FLAGS@) _files; return ;;
+    esac
+
+    case "$cmd $sub" in
+@DYNAMIC_ARMS@    esac
+
+    if [[ $cur == -* ]]; then
+        local -a flags=(@GLOBAL_FLAGS@)
+        case "$cmd" in
+@FLAG_ARMS@        esac
+        compadd -a flags
+    elif [[ -z $cmd ]]; then
+        compadd @COMMANDS@
+    elif [[ -z $sub ]]; then
+        case "$cmd" in
+@SUB_ARMS@        esac
+    else
+        case "$cmd $sub" in
+@NESTED_ARMS@        esac
+    fi
+}
+
+if [ "$funcstack[1]" = "_agent-browser" ]; then
+    _agent-browser "$@"
+else
+    compdef _agent-browser agent-browser
+fi
+"#;
+
+fn zsh() -> String {
+    let compadd = |words: &str| format!("compadd {}", words);
+    let values = |kind: &str| format!("_agent_browser_values {}; return", kind);
+    let indent = " ".repeat(12);
+    ZSH_TEMPLATE
+        .replace("@VALUE_FLAGS@", &value_flags_pattern())
+        .replace(
+            "@CHOICE_ARMS@",
+            &case_arms(&choice_flag_items(), "        ", |w| {
+                format!("{}; return", compadd(w))
+            }),
+        )
+        .replace("@DYNAMIC_ARMS@", &dynamic_arms("        ", values))
+        .replace("@GLOBAL_FLAGS@", &global_flags().join(" "))
+        .replace("@COMMANDS@", &command_names().join(" "))
+        .replace(
+            "@FLAG_ARMS@",
+            &case_arms(&flag_items(), &indent, |w| format!("flags+=({})", w)),
+        )
+        .replace(
+            "@SUB_ARMS@",
+            &case_arms(&subcommand_items(), &indent, compadd),
+        )
+        .replace(
+            "@NESTED_ARMS@",
+            &case_arms(&nested_items(), &indent, compadd),
+        )
+}
+
+/// fish `-l name` / `-s n` option for a flag
+fn fish_flag(flag: &str) -> String {
+    match flag.strip_prefix("--") {
+        Some(long) => format!("-l {}", long),
+        None => format!("-s {}", flag.trim_start_matches('-')),
+    }
+}
+
+fn fish() -> String {
+    let mut out = String::from(
+        "# fish completion for agent-browser\n\
+         # Install with: agent-browser completions fish > ~/.config/fish/completions/agent-browser.fish\n\n\
+         complete -c agent-browser -f\n",
+    );
+    let commands = command_names().join(" ");
+    out.push_str(&format!("set -l commands {}\n", commands));
+    out.push_str("complete -c agent-browser -n \"not __fish_seen_subcommand_from $commands\" -a \"$commands\"\n\n");
+
+    for flag in GLOBAL_FLAGS.iter().chain(&["--help", "--version"]) {
+        out.push_str(&format!("complete -c agent-browser {}\n", fish_flag(flag)));
+    }
+    for flag in GLOBAL_FLAGS_WITH_VALUE.iter() {
+        let values = match *flag {
+            "--session" | "--sessions" => " -a \"(agent-browser __complete sessions)\"",
+            "--env" => " -a \"(agent-browser __complete envs)\"",
+            f if PATH_FLAGS.contains(&f) => " -F",
+            _ => "",
+        };
+        out.push_str(&format!(
+            "complete -c agent-browser {} -r{}\n",
+            fish_flag(flag),
+            values
+        ));
+    }
+    out.push('\n');
+
+    for c in registry::COMMANDS {
+        let names = case_pattern(c).replace('|', " ");
+        let words = next_words(c).join(" ");
+        if !words.is_empty() {
+            out.push_str(&format!(
+                "complete -c agent-browser -n \"__fish_seen_subcommand_from {}; and not __fish_seen_subcommand_from {}\" -a \"{}\"\n",
+                names, words, words
+            ));
+        }
+        for flag in all_flags(c) {
+            let value = match flag.value.map(|v| v.ty) {
+                Some(ArgType::Choice(options)) => format!(" -r -a \"{}\"", options.join(" ")),
+                Some(_) => " -r".to_string(),
+                None => String::new(),
+            };
+            let short = flag
+                .short
+                .map_or(String::new(), |s| format!(" {}", fish_flag(s)));
+            out.push_str(&format!(
+                "complete -c agent-browser -n \"__fish_seen_subcommand_from {}\" {}{}{}\n",
+                names,
+                fish_flag(flag.long),
+                short,
+                value
+            ));
+        }
+    }
+    out.push('\n');
+
+    for c in registry::COMMANDS {
+        for sub in c.subcommands {
+            let condition = format!(
+                "__fish_seen_subcommand_from {}; and __fish_seen_subcommand_from {}",
+                case_pattern(c).replace('|', " "),
+                case_pattern(sub).replace('|', " ")
+            );
+            let words = next_words(sub).join(" ");
+            if !words.is_empty() {
+                out.push_str(&format!(
+                    "complete -c agent-browser -n \"{}; and not __fish_seen_subcommand_from {}\" -a \"{}\"\n",
+                    condition, words, words
+                ));
+            }
+            if let Some(kind) = dynamic_kind(sub) {
+                out.push_str(&format!(
+                    "complete -c agent-browser -n \"{}\" -a \"(agent-browser __complete {})\"\n",
+                    condition, kind
+                ));
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_cover_commands_and_global_flags() {
+        for script in [bash(), zsh(), fish()] {
+            for name in command_names() {
+                assert!(script.contains(name), "missing command {}", name);
+            }
+            for flag in GLOBAL_FLAGS.iter().chain(GLOBAL_FLAGS_WITH_VALUE.iter()) {
+                let flag = flag.trim_start_matches('-');
+                assert!(script.contains(flag), "missing flag {}", flag);
+            }
+            for placeholder in ["@VALUE_FLAGS@", "@GLOBAL_FLAGS@", "@COMMANDS@", "_ARMS@"] {
+                assert!(!script.contains(placeholder), "unreplaced {}", placeholder);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bash_subcommand_arms() {
+        let script = bash();
+        assert!(script.contains("scrollintoview scrollinto"));
+        assert!(script.contains(r#"cookies) flags+=" --url --domain"#));
+        assert!(script.contains(
+            r#"get) COMPREPLY=($(compgen -W "text html value attr url title count box styles" -- "$cur")) ;;"#
+        ));
+        assert!(script.contains(
+            r#""storage local") COMPREPLY=($(compgen -W "get set clear" -- "$cur")) ;;"#
+        ));
+        assert!(script.contains("_agent_browser_values devices"));
+    }
+
+    #[test]
+    fn test_fish_flags() {
+        assert_eq!(fish_flag("--session"), "-l session");
+        assert_eq!(fish_flag("-p"), "-s p");
+        let script = fish();
+        assert!(script.contains(
+            "complete -c agent-browser -l session -r -a \"(agent-browser __complete sessions)\""
+        ));
+        assert!(script.contains("complete -c agent-browser -l profile -r -F"));
+    }
+}
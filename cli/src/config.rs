@@ -0,0 +1,707 @@
+//! Default flags from configuration files.
+//!
+//! A project `agent-browser.toml` (found by walking up from the current
+//! directory) and a user `~/.config/agent-browser/config.toml` can set the
+//! launch options otherwise passed as flags. Precedence is CLI > env >
+//! project > user; `config show` prints the merged result and the source of
+//! each value.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+use crate::color;
+use crate::commands::ParseError;
+use crate::flags::Flags;
+use crate::output::print_parse_error;
+use crate::proxy;
+
+pub const PROJECT_FILE: &str = "agent-browser.toml";
+
+/// Keys accepted in a configuration file
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub session: Option<String>,
+    pub headed: Option<bool>,
+    pub proxy: Option<String>,
+    pub proxy_bypass: Option<String>,
+    pub proxy_pac: Option<String>,
+    pub proxy_credentials_file: Option<String>,
+    pub proxy_pool: Option<String>,
+    pub proxy_pool_strategy: Option<String>,
+    pub user_agent: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub extensions: Option<Vec<String>>,
+    pub executable_path: Option<String>,
+    pub profile: Option<String>,
+    pub state: Option<String>,
+    pub headers: Option<toml::Table>,
+    pub ignore_https_errors: Option<bool>,
+    /// `false` accepts unknown flags as arguments, as before strict parsing
+    pub strict: Option<bool>,
+    /// Named profiles selected with `--env <name>`
+    pub env: Option<BTreeMap<String, EnvProfile>>,
+    /// Command aliases: name to `;`-separated commands
+    pub aliases: Option<BTreeMap<String, String>>,
+}
+
+/// An `[env.<name>]` table: settings for one target environment, layered
+/// over the rest of the file
+#[derive(Deserialize, Default, Clone, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct EnvProfile {
+    /// Base for `open` paths such as `/login`
+    pub base_url: Option<String>,
+    pub proxy: Option<String>,
+    /// Added to (and overriding) the file's `headers`
+    pub headers: Option<toml::Table>,
+    pub credentials: Option<Credentials>,
+    pub viewport: Option<Viewport>,
+    pub locale: Option<String>,
+    pub state: Option<String>,
+}
+
+/// HTTP basic auth credentials
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid config {}: {}",
+            self.path.display(),
+            self.message
+        )
+    }
+}
+
+/// The project and user configuration files, either of which may be absent
+#[derive(Default)]
+pub struct Config {
+    pub project: Option<(PathBuf, FileConfig)>,
+    pub user: Option<(PathBuf, FileConfig)>,
+}
+
+impl Config {
+    pub fn load() -> Result<Config, ConfigError> {
+        let cwd = env::current_dir().unwrap_or_default();
+        Ok(Config {
+            project: find_project_file(&cwd).map(load_file).transpose()?,
+            user: user_config_path()
+                .filter(|p| p.is_file())
+                .map(load_file)
+                .transpose()?,
+        })
+    }
+
+    /// First value set by the project file, then the user file, along with
+    /// the file it came from
+    pub fn pick<'a, T>(
+        &'a self,
+        get: impl Fn(&'a FileConfig) -> Option<T>,
+    ) -> Option<(T, &'a Path)> {
+        [&self.project, &self.user]
+            .into_iter()
+            .flatten()
+            .find_map(|(path, file)| get(file).map(|v| (v, path.as_path())))
+    }
+
+    pub fn get<'a, T>(&'a self, get: impl Fn(&'a FileConfig) -> Option<T>) -> Option<T> {
+        self.pick(get).map(|(v, _)| v)
+    }
+
+    /// The `[env.<name>]` profile, from the project file if it defines one
+    /// and otherwise from the user file
+    pub fn profile(&self, name: &str) -> Option<(&EnvProfile, &Path)> {
+        self.pick(|c| c.env.as_ref()?.get(name))
+    }
+
+    /// The alias `name`, from the project file if it defines one and
+    /// otherwise from the user file
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.get(|c| c.aliases.as_ref()?.get(name).map(|s| s.as_str()))
+    }
+
+    /// Names of every profile defined in either file
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = [&self.project, &self.user]
+            .into_iter()
+            .flatten()
+            .filter_map(|(_, file)| file.env.as_ref())
+            .flat_map(|profiles| profiles.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+fn load_file(path: PathBuf) -> Result<(PathBuf, FileConfig), ConfigError> {
+    let parsed = fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|s| toml::from_str(&s).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(file) => Ok((path, file)),
+        Err(message) => Err(ConfigError {
+            path,
+            message: message.trim().to_string(),
+        }),
+    }
+}
+
+fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|p| p.is_file())
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let base = match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => dirs::home_dir()?.join(".config"),
+    };
+    Some(base.join("agent-browser").join("config.toml"))
+}
+
+/// `headers` tables as the JSON string `--headers` takes. Later tables
+/// override earlier ones key by key.
+pub fn headers_json(tables: &[&toml::Table]) -> Option<String> {
+    if tables.is_empty() {
+        return None;
+    }
+    let mut merged = toml::Table::new();
+    for table in tables {
+        merged.extend(table.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+    serde_json::to_string(&merged).ok()
+}
+
+/// `args` list in the newline-separated form of `--args`, so individual
+/// arguments may contain commas
+pub fn args_string(args: &[String]) -> String {
+    args.join("\n")
+}
+
+/// A configurable setting: its file key, CLI flag and env var
+struct Setting {
+    key: &'static str,
+    flag: &'static str,
+    env: Option<&'static str>,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "env",
+        flag: "--env",
+        env: Some("AGENT_BROWSER_ENV"),
+    },
+    Setting {
+        key: "session",
+        flag: "--session",
+        env: Some("AGENT_BROWSER_SESSION"),
+    },
+    Setting {
+        key: "headed",
+        flag: "--headed",
+        env: None,
+    },
+    Setting {
+        key: "proxy",
+        flag: "--proxy",
+        env: Some("AGENT_BROWSER_PROXY"),
+    },
+    Setting {
+        key: "proxy_bypass",
+        flag: "--proxy-bypass",
+        env: Some("AGENT_BROWSER_PROXY_BYPASS"),
+    },
+    Setting {
+        key: "proxy_pac",
+        flag: "--proxy-pac",
+        env: Some("AGENT_BROWSER_PROXY_PAC"),
+    },
+    Setting {
+        key: "proxy_credentials_file",
+        flag: "--proxy-credentials-file",
+        env: Some("AGENT_BROWSER_PROXY_CREDENTIALS_FILE"),
+    },
+    Setting {
+        key: "proxy_pool",
+        flag: "--proxy-pool",
+        env: Some("AGENT_BROWSER_PROXY_POOL"),
+    },
+    Setting {
+        key: "proxy_pool_strategy",
+        flag: "--proxy-pool-strategy",
+        env: Some("AGENT_BROWSER_PROXY_POOL_STRATEGY"),
+    },
+    Setting {
+        key: "user_agent",
+        flag: "--user-agent",
+        env: Some("AGENT_BROWSER_USER_AGENT"),
+    },
+    Setting {
+        key: "args",
+        flag: "--args",
+        env: Some("AGENT_BROWSER_ARGS"),
+    },
+    Setting {
+        key: "extensions",
+        flag: "--extension",
+        env: Some("AGENT_BROWSER_EXTENSIONS"),
+    },
+    Setting {
+        key: "executable_path",
+        flag: "--executable-path",
+        env: Some("AGENT_BROWSER_EXECUTABLE_PATH"),
+    },
+    Setting {
+        key: "profile",
+        flag: "--profile",
+        env: Some("AGENT_BROWSER_PROFILE"),
+    },
+    Setting {
+        key: "state",
+        flag: "--state",
+        env: Some("AGENT_BROWSER_STATE"),
+    },
+    Setting {
+        key: "headers",
+        flag: "--headers",
+        env: None,
+    },
+    Setting {
+        key: "ignore_https_errors",
+        flag: "--ignore-https-errors",
+        env: None,
+    },
+    Setting {
+        key: "strict",
+        flag: "--no-strict",
+        env: Some("AGENT_BROWSER_STRICT"),
+    },
+    Setting {
+        key: "base_url",
+        flag: "",
+        env: None,
+    },
+    Setting {
+        key: "credentials",
+        flag: "",
+        env: None,
+    },
+    Setting {
+        key: "viewport",
+        flag: "",
+        env: None,
+    },
+    Setting {
+        key: "locale",
+        flag: "",
+        env: None,
+    },
+];
+
+/// Whether the file sets `key`
+fn file_sets(file: &FileConfig, key: &str) -> bool {
+    match key {
+        "session" => file.session.is_some(),
+        "headed" => file.headed.is_some(),
+        "proxy" => file.proxy.is_some(),
+        "proxy_bypass" => file.proxy_bypass.is_some(),
+        "proxy_pac" => file.proxy_pac.is_some(),
+        "proxy_credentials_file" => file.proxy_credentials_file.is_some(),
+        "proxy_pool" => file.proxy_pool.is_some(),
+        "proxy_pool_strategy" => file.proxy_pool_strategy.is_some(),
+        "user_agent" => file.user_agent.is_some(),
+        "args" => file.args.is_some(),
+        "extensions" => file.extensions.is_some(),
+        "executable_path" => file.executable_path.is_some(),
+        "profile" => file.profile.is_some(),
+        "state" => file.state.is_some(),
+        "headers" => file.headers.is_some(),
+        "ignore_https_errors" => file.ignore_https_errors.is_some(),
+        "strict" => file.strict.is_some(),
+        _ => false,
+    }
+}
+
+/// Whether the profile sets `key`
+fn profile_sets(profile: &EnvProfile, key: &str) -> bool {
+    match key {
+        "base_url" => profile.base_url.is_some(),
+        "proxy" => profile.proxy.is_some(),
+        "headers" => profile.headers.is_some(),
+        "credentials" => profile.credentials.is_some(),
+        "viewport" => profile.viewport.is_some(),
+        "locale" => profile.locale.is_some(),
+        "state" => profile.state.is_some(),
+        _ => false,
+    }
+}
+
+/// Header names with the values hidden, as those are usually credentials
+fn redact_headers(headers: &str) -> Value {
+    match serde_json::from_str::<serde_json::Map<String, Value>>(headers) {
+        Ok(map) => Value::Object(map.into_iter().map(|(k, _)| (k, json!("***"))).collect()),
+        Err(_) => json!("***"),
+    }
+}
+
+/// Effective value of `key` after merging every source
+fn effective_value(flags: &Flags, key: &str) -> Value {
+    match key {
+        "session" => json!(flags.session),
+        "headed" => json!(flags.headed),
+        "proxy" => json!(flags.proxy.as_deref().map(proxy::redact)),
+        "proxy_bypass" => json!(flags.proxy_bypass),
+        "proxy_pac" => json!(flags.proxy_pac),
+        "proxy_credentials_file" => json!(flags.proxy_credentials_file),
+        "proxy_pool" => json!(flags.proxy_pool),
+        "proxy_pool_strategy" => json!(flags.proxy_pool_strategy),
+        "user_agent" => json!(flags.user_agent),
+        "args" => json!(flags.args),
+        "extensions" => json!(flags.extensions),
+        "executable_path" => json!(flags.executable_path),
+        "profile" => json!(flags.profile),
+        "state" => json!(flags.state),
+        "headers" => json!(flags.headers.as_deref().map(redact_headers)),
+        "ignore_https_errors" => json!(flags.ignore_https_errors),
+        "strict" => json!(flags.strict),
+        "env" => json!(flags.env),
+        "base_url" => json!(flags.base_url),
+        // Never print the password
+        "credentials" => json!(flags
+            .credentials
+            .as_ref()
+            .map(|c| format!("{}:***", c.username))),
+        "viewport" => json!(flags.viewport.map(|v| format!("{}x{}", v.width, v.height))),
+        "locale" => json!(flags.locale),
+        _ => Value::Null,
+    }
+}
+
+/// The CLI or env var source of a setting, when it didn't come from a config
+/// file or a default
+fn explicit_source(setting: &Setting, args: &[String]) -> Option<String> {
+    if !setting.flag.is_empty() && args.iter().any(|a| a == setting.flag) {
+        return Some("cli".to_string());
+    }
+    let var = setting.env?;
+    env::var(var).is_ok().then(|| format!("env {}", var))
+}
+
+/// Flags of the settings given on the command line or through their env var
+pub fn explicit_flags(args: &[String]) -> Vec<&'static str> {
+    SETTINGS
+        .iter()
+        .filter(|s| explicit_source(s, args).is_some())
+        .map(|s| s.flag)
+        .collect()
+}
+
+/// Where the effective value of a setting came from
+fn source_of(setting: &Setting, args: &[String], flags: &Flags, config: &Config) -> String {
+    if let Some(source) = explicit_source(setting, args) {
+        return source;
+    }
+    if let Some(name) = &flags.env {
+        if let Some((profile, path)) = config.profile(name) {
+            if profile_sets(profile, setting.key) {
+                return format!("env.{} {}", name, path.display());
+            }
+        }
+    }
+    for (label, file) in [("project", &config.project), ("user", &config.user)] {
+        if let Some((path, f)) = file {
+            if file_sets(f, setting.key) {
+                return format!("{} {}", label, path.display());
+            }
+        }
+    }
+    let standard = match setting.key {
+        "proxy" if flags.proxy.is_some() => proxy::standard_proxy(),
+        "proxy_bypass" if flags.proxy_bypass.is_some() => proxy::standard_no_proxy(),
+        _ => None,
+    };
+    match standard {
+        Some((var, _)) => format!("env {}", var),
+        None => "default".to_string(),
+    }
+}
+
+pub fn run_config(args: &[String], raw_args: &[String], flags: &Flags, config: &Config) {
+    match args.get(1).map(|s| s.as_str()) {
+        Some("show") | None => show(raw_args, flags, config),
+        Some(sub) => {
+            print_parse_error(
+                &ParseError::UnknownSubcommand {
+                    subcommand: sub.to_string(),
+                    valid_options: vec!["show"],
+                },
+                flags.json,
+            );
+            exit(1);
+        }
+    }
+}
+
+fn show(args: &[String], flags: &Flags, config: &Config) {
+    let file_path =
+        |f: &Option<(PathBuf, FileConfig)>| f.as_ref().map(|(p, _)| p.display().to_string());
+
+    if flags.json {
+        let values: serde_json::Map<String, Value> = SETTINGS
+            .iter()
+            .map(|s| {
+                (
+                    s.key.to_string(),
+                    json!({
+                        "value": effective_value(flags, s.key),
+                        "source": source_of(s, args, flags, config),
+                    }),
+                )
+            })
+            .collect();
+        println!(
+            "{}",
+            json!({
+                "success": true,
+                "data": {
+                    "files": {
+                        "project": file_path(&config.project),
+                        "user": file_path(&config.user),
+                    },
+                    "values": values,
+                }
+            })
+        );
+        return;
+    }
+
+    for (label, file) in [("project", &config.project), ("user", &config.user)] {
+        let path = file_path(file).unwrap_or_else(|| "(none)".to_string());
+        println!("{} {}", color::dim(&format!("{:<8}", label)), path);
+    }
+    println!();
+    for setting in SETTINGS {
+        let value = match effective_value(flags, setting.key) {
+            Value::Null => "-".to_string(),
+            Value::String(s) => s,
+            v => v.to_string(),
+        };
+        println!(
+            "{:<22} {:<28} {}",
+            setting.key,
+            value,
+            color::dim(&format!("({})", source_of(setting, args, flags, config)))
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("ab-config-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_find_project_file_walks_up() {
+        let root = temp_dir("walk");
+        let nested = root.join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(root.join(PROJECT_FILE), "headed = true\n").unwrap();
+
+        assert_eq!(find_project_file(&nested), Some(root.join(PROJECT_FILE)));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_load_file_parses_keys() {
+        let root = temp_dir("parse");
+        let path = root.join(PROJECT_FILE);
+        fs::write(
+            &path,
+            r#"
+session = "qa"
+headed = true
+args = ["--window-size=1280,720", "--mute-audio"]
+ignore_https_errors = true
+
+[headers]
+Authorization = "Bearer token"
+"#,
+        )
+        .unwrap();
+
+        let (_, file) = load_file(path).unwrap();
+        assert_eq!(file.session.as_deref(), Some("qa"));
+        assert_eq!(file.headed, Some(true));
+        assert_eq!(
+            args_string(file.args.as_deref().unwrap()),
+            "--window-size=1280,720\n--mute-audio"
+        );
+        assert_eq!(
+            headers_json(&[file.headers.as_ref().unwrap()]).as_deref(),
+            Some(r#"{"Authorization":"Bearer token"}"#)
+        );
+        assert_eq!(file.ignore_https_errors, Some(true));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_load_file_rejects_unknown_keys() {
+        let root = temp_dir("unknown");
+        let path = root.join(PROJECT_FILE);
+        fs::write(&path, "hedaed = true\n").unwrap();
+
+        let err = load_file(path.clone()).unwrap_err();
+        assert_eq!(err.path, path);
+        assert!(err.message.contains("hedaed"));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_project_overrides_user() {
+        let config = Config {
+            project: Some((
+                PathBuf::from("/p/agent-browser.toml"),
+                FileConfig {
+                    session: Some("project".to_string()),
+                    ..Default::default()
+                },
+            )),
+            user: Some((
+                PathBuf::from("/u/config.toml"),
+                FileConfig {
+                    session: Some("user".to_string()),
+                    proxy: Some("http://proxy:8080".to_string()),
+                    ..Default::default()
+                },
+            )),
+        };
+
+        let (session, path) = config.pick(|c| c.session.clone()).unwrap();
+        assert_eq!(session, "project");
+        assert_eq!(path, Path::new("/p/agent-browser.toml"));
+        assert_eq!(
+            config.get(|c| c.proxy.clone()).as_deref(),
+            Some("http://proxy:8080")
+        );
+        assert!(config.get(|c| c.profile.clone()).is_none());
+    }
+
+    #[test]
+    fn test_show_redacts_header_values() {
+        let args: Vec<String> = ["--headers", r#"{"Authorization":"Bearer t0ken"}"#]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let flags = crate::flags::parse_flags(&args, &Config::default());
+        assert_eq!(
+            effective_value(&flags, "headers"),
+            json!({ "Authorization": "***" })
+        );
+    }
+
+    #[test]
+    fn test_explicit_flags_exclude_config_defaults() {
+        let config = Config {
+            project: Some((
+                PathBuf::from("/p/agent-browser.toml"),
+                FileConfig {
+                    headed: Some(true),
+                    ignore_https_errors: Some(true),
+                    ..Default::default()
+                },
+            )),
+            user: None,
+        };
+
+        let flags = crate::flags::parse_flags(&["--headed".to_string()], &config);
+        assert!(flags.ignore_https_errors);
+        assert!(flags.explicit.contains(&"--headed"));
+        assert!(!flags.explicit.contains(&"--ignore-https-errors"));
+    }
+
+    #[test]
+    fn test_env_profiles() {
+        let root = temp_dir("profiles");
+        let path = root.join(PROJECT_FILE);
+        fs::write(
+            &path,
+            r#"
+[headers]
+X-Team = "qa"
+
+[env.staging]
+base_url = "https://staging.example.com"
+viewport = { width = 1440, height = 900 }
+locale = "de-DE"
+credentials = { username = "qa", password = "secret" }
+
+[env.staging.headers]
+X-Env = "staging"
+
+[env.local]
+base_url = "http://localhost:3000"
+"#,
+        )
+        .unwrap();
+
+        let config = Config {
+            project: Some(load_file(path.clone()).unwrap()),
+            user: None,
+        };
+        assert_eq!(config.profile_names(), vec!["local", "staging"]);
+
+        let (staging, from) = config.profile("staging").unwrap();
+        assert_eq!(from, path.as_path());
+        assert_eq!(
+            staging.viewport,
+            Some(Viewport {
+                width: 1440,
+                height: 900
+            })
+        );
+        assert_eq!(staging.credentials.as_ref().unwrap().username, "qa");
+
+        let base = config.get(|c| c.headers.as_ref()).unwrap();
+        assert_eq!(
+            headers_json(&[base, staging.headers.as_ref().unwrap()]).as_deref(),
+            Some(r#"{"X-Env":"staging","X-Team":"qa"}"#)
+        );
+        assert!(config.profile("prod").is_none());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,17 +1,28 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::env;
+use std::fmt;
 use std::fs;
-use std::io::{BufRead, BufReader, Read, Write};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
 use std::net::TcpStream;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
 #[cfg(unix)]
 use std::os::unix::net::UnixStream;
 
+#[cfg(windows)]
+use windows_sys::Win32::Foundation::CloseHandle;
+#[cfg(windows)]
+use windows_sys::Win32::System::Threading::{OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION};
+
+use crate::commands::gen_id;
+use crate::flags::Flags;
+use crate::proxy_pool::fnv1a;
+
 #[derive(Serialize)]
 #[allow(dead_code)]
 pub struct Request {
@@ -28,6 +39,51 @@ pub struct Response {
     pub error: Option<String>,
 }
 
+/// Error talking to (or starting) a session daemon
+#[derive(Debug)]
+pub enum DaemonError {
+    /// The daemon died: the connection closed without a response, or a
+    /// previous daemon left a stale pid file and socket behind
+    Crashed { session: String, detail: String },
+    /// Any other failure (daemon not found, timeouts, invalid responses)
+    Other(String),
+}
+
+impl DaemonError {
+    /// Machine-readable error type for `--json` output
+    pub fn error_type(&self) -> &'static str {
+        match self {
+            DaemonError::Crashed { .. } => "daemon_crashed",
+            DaemonError::Other(_) => "daemon_error",
+        }
+    }
+}
+
+impl fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DaemonError::Crashed { session, detail } => write!(
+                f,
+                "Daemon for session '{}' crashed ({}). Browser state was lost; use --auto-recover to relaunch from the last saved state",
+                session, detail
+            ),
+            DaemonError::Other(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<String> for DaemonError {
+    fn from(msg: String) -> Self {
+        DaemonError::Other(msg)
+    }
+}
+
+impl From<&str> for DaemonError {
+    fn from(msg: &str) -> Self {
+        DaemonError::Other(msg.to_string())
+    }
+}
+
 #[allow(dead_code)]
 pub enum Connection {
     #[cfg(unix)]
@@ -116,6 +172,18 @@ fn get_pid_path(session: &str) -> PathBuf {
     get_socket_dir().join(format!("{}.pid", session))
 }
 
+fn get_recovery_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.recovery.json", session))
+}
+
+fn get_recovery_state_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.state.json", session))
+}
+
+fn get_launch_config_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.launch", session))
+}
+
 #[cfg(windows)]
 fn get_port_path(session: &str) -> PathBuf {
     get_socket_dir().join(format!("{}.port", session))
@@ -133,7 +201,7 @@ fn get_port_for_session(session: &str) -> u16 {
 }
 
 #[cfg(unix)]
-fn is_daemon_running(session: &str) -> bool {
+pub fn is_daemon_running(session: &str) -> bool {
     let pid_path = get_pid_path(session);
     if !pid_path.exists() {
         return false;
@@ -149,7 +217,7 @@ fn is_daemon_running(session: &str) -> bool {
 }
 
 #[cfg(windows)]
-fn is_daemon_running(session: &str) -> bool {
+pub fn is_daemon_running(session: &str) -> bool {
     let pid_path = get_pid_path(session);
     if !pid_path.exists() {
         return false;
@@ -162,6 +230,126 @@ fn is_daemon_running(session: &str) -> bool {
     .is_ok()
 }
 
+fn is_process_running(pid: u32) -> bool {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(pid as i32, 0) == 0
+    }
+    #[cfg(windows)]
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle != 0 {
+            CloseHandle(handle);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// List sessions whose daemon is running, sorted by name
+pub fn list_sessions() -> Vec<String> {
+    let socket_dir = get_socket_dir();
+    let mut sessions: Vec<String> = Vec::new();
+
+    if let Ok(entries) = fs::read_dir(&socket_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Look for pid files in socket directory
+            let Some(session_name) = name.strip_suffix(".pid") else {
+                continue;
+            };
+            if session_name.is_empty() {
+                continue;
+            }
+            // Check if session is actually running
+            let running = fs::read_to_string(socket_dir.join(&name))
+                .ok()
+                .and_then(|pid_str| pid_str.trim().parse::<u32>().ok())
+                .is_some_and(is_process_running);
+            if running {
+                sessions.push(session_name.to_string());
+            }
+        }
+    }
+
+    sessions.sort();
+    sessions
+}
+
+/// Match a session name against a pattern where `*` matches any run of
+/// characters and `?` a single character
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+    let (mut pi, mut ni) = (0, 0);
+    // Position of the last `*` and the name index it was tried against
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            backtrack = Some((pi, ni));
+            pi += 1;
+        } else if let Some((star, matched)) = backtrack {
+            pi = star + 1;
+            ni = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Expand a comma-separated session list such as `a,b` or `pool-*`.
+/// Patterns match running sessions; plain names are used as-is.
+pub fn resolve_sessions(spec: &str) -> Vec<String> {
+    let mut running: Option<Vec<String>> = None;
+    let mut sessions: Vec<String> = Vec::new();
+
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let matches = if part.contains(['*', '?']) {
+            running
+                .get_or_insert_with(list_sessions)
+                .iter()
+                .filter(|s| glob_match(part, s))
+                .cloned()
+                .collect()
+        } else {
+            vec![part.to_string()]
+        };
+        for session in matches {
+            if !sessions.contains(&session) {
+                sessions.push(session);
+            }
+        }
+    }
+    sessions
+}
+
+/// Detect files left behind by a daemon that died without cleaning up
+/// (OOM kill, Chromium crash). A daemon that exits normally removes its pid
+/// file and socket, so a pid file without a live process means it crashed.
+/// The stale files are removed so the next daemon can start cleanly.
+fn take_stale_daemon(session: &str) -> bool {
+    let pid_path = get_pid_path(session);
+    if !pid_path.exists() || is_daemon_running(session) {
+        return false;
+    }
+
+    #[cfg(unix)]
+    let endpoint = get_socket_path(session);
+    #[cfg(windows)]
+    let endpoint = get_port_path(session);
+
+    let stale = endpoint.exists();
+    let _ = fs::remove_file(&pid_path);
+    let _ = fs::remove_file(&endpoint);
+    stale
+}
+
 fn daemon_ready(session: &str) -> bool {
     #[cfg(unix)]
     {
@@ -197,13 +385,20 @@ pub fn ensure_daemon(
     ignore_https_errors: bool,
     profile: Option<&str>,
     state: Option<&str>,
-) -> Result<DaemonResult, String> {
+) -> Result<DaemonResult, DaemonError> {
     if is_daemon_running(session) && daemon_ready(session) {
         return Ok(DaemonResult {
             already_running: true,
         });
     }
 
+    if take_stale_daemon(session) {
+        return Err(DaemonError::Crashed {
+            session: session.to_string(),
+            detail: "stale pid file".to_string(),
+        });
+    }
+
     // Ensure socket directory exists
     let socket_dir = get_socket_dir();
     if !socket_dir.exists() {
@@ -229,6 +424,9 @@ pub fn ensure_daemon(
         daemon_paths.insert(1, home_path.join("daemon.js"));
     }
 
+    // A new daemon starts unconfigured; forget what the previous one applied
+    clear_launch_config(session);
+
     let daemon_path = daemon_paths
         .iter()
         .find(|p| p.exists())
@@ -289,6 +487,10 @@ pub fn ensure_daemon(
             cmd.pre_exec(|| {
                 // Create new session (detach from terminal)
                 libc::setsid();
+                // Don't inherit the CLI's blocked SIGINT (see main)
+                let mut set: libc::sigset_t = std::mem::zeroed();
+                libc::sigemptyset(&mut set);
+                libc::pthread_sigmask(libc::SIG_SETMASK, &set, std::ptr::null_mut());
                 Ok(())
             });
         }
@@ -372,7 +574,32 @@ pub fn ensure_daemon(
         thread::sleep(Duration::from_millis(100));
     }
 
-    Err("Daemon failed to start".to_string())
+    Err("Daemon failed to start".into())
+}
+
+/// Start (or reuse) the daemon for `session` with the launch options in `flags`
+pub fn start_daemon(session: &str, flags: &Flags) -> Result<DaemonResult, DaemonError> {
+    ensure_daemon(
+        session,
+        flags.headed,
+        flags.executable_path.as_deref(),
+        &flags.extensions,
+        flags.args.as_deref(),
+        flags.user_agent.as_deref(),
+        flags.proxy.as_deref(),
+        flags.proxy_bypass.as_deref(),
+        flags.ignore_https_errors,
+        flags.profile.as_deref(),
+        flags.state.as_deref(),
+    )
+}
+
+/// Start a new daemon for a session whose daemon just crashed. A daemon that
+/// died mid-command leaves its pid and socket files behind, which would
+/// otherwise be reported as the same crash again.
+pub fn restart_daemon(session: &str, flags: &Flags) -> Result<DaemonResult, DaemonError> {
+    take_stale_daemon(session);
+    start_daemon(session, flags)
 }
 
 fn connect(session: &str) -> Result<Connection, String> {
@@ -392,14 +619,73 @@ fn connect(session: &str) -> Result<Connection, String> {
     }
 }
 
-pub fn send_command(cmd: Value, session: &str) -> Result<Response, String> {
-    let mut stream = connect(session)?;
+/// Requests awaiting a response as (session, id), so an interrupted CLI can
+/// ask the daemons to cancel them
+static IN_FLIGHT: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// Registers a request in `IN_FLIGHT` until dropped
+struct InFlight {
+    session: String,
+    id: String,
+}
+
+impl InFlight {
+    fn track(session: &str, cmd: &Value) -> Option<Self> {
+        let id = cmd.get("id")?.as_str()?.to_string();
+        in_flight().push((session.to_string(), id.clone()));
+        Some(InFlight {
+            session: session.to_string(),
+            id,
+        })
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        in_flight().retain(|(s, id)| *s != self.session || *id != self.id);
+    }
+}
+
+fn in_flight() -> std::sync::MutexGuard<'static, Vec<(String, String)>> {
+    IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Ask each daemon to abandon the requests this process is waiting on, over
+/// a fresh connection. Best effort: used while exiting on Ctrl-C.
+pub fn cancel_in_flight() {
+    let pending = in_flight().clone();
+    for (session, id) in pending {
+        let Ok(mut stream) = connect(&session) else {
+            continue;
+        };
+        stream.set_read_timeout(Some(Duration::from_secs(2))).ok();
+        stream.set_write_timeout(Some(Duration::from_secs(2))).ok();
+        let cancel = json!({ "id": gen_id(), "action": "cancel", "target": id });
+        if stream.write_all(format!("{}\n", cancel).as_bytes()).is_ok() {
+            // Wait for the ack so the cancel isn't lost when we exit
+            let _ = BufReader::new(stream).read_line(&mut String::new());
+        }
+    }
+}
+
+pub fn send_command(cmd: Value, session: &str) -> Result<Response, DaemonError> {
+    let mut stream = match connect(session) {
+        Ok(stream) => stream,
+        Err(e) if take_stale_daemon(session) => {
+            return Err(DaemonError::Crashed {
+                session: session.to_string(),
+                detail: e,
+            })
+        }
+        Err(e) => return Err(e.into()),
+    };
 
     stream.set_read_timeout(Some(Duration::from_secs(30))).ok();
     stream.set_write_timeout(Some(Duration::from_secs(5))).ok();
 
     let mut json_str = serde_json::to_string(&cmd).map_err(|e| e.to_string())?;
     json_str.push('\n');
+    let _in_flight = InFlight::track(session, &cmd);
 
     stream
         .write_all(json_str.as_bytes())
@@ -407,11 +693,103 @@ pub fn send_command(cmd: Value, session: &str) -> Result<Response, String> {
 
     let mut reader = BufReader::new(stream);
     let mut response_line = String::new();
-    reader
-        .read_line(&mut response_line)
-        .map_err(|e| format!("Failed to read: {}", e))?;
+    let crashed = |detail: String| DaemonError::Crashed {
+        session: session.to_string(),
+        detail,
+    };
+    match reader.read_line(&mut response_line) {
+        // EOF before a response line: the daemon went away mid-command
+        Ok(0) => return Err(crashed("connection closed without a response".to_string())),
+        Ok(_) => {}
+        Err(e)
+            if matches!(
+                e.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe
+            ) =>
+        {
+            return Err(crashed(format!("Failed to read: {}", e)))
+        }
+        Err(e) => return Err(format!("Failed to read: {}", e).into()),
+    }
+
+    serde_json::from_str(&response_line).map_err(|e| format!("Invalid response: {}", e).into())
+}
+
+/// Fingerprint of a launch config (the `launch` command without its id).
+/// Only this is kept on disk: the config itself can hold proxy and HTTP
+/// credentials.
+fn launch_config_hash(config: &Value) -> String {
+    format!("{:016x}", fnv1a(&config.to_string()))
+}
+
+/// Whether `config` is the launch config last applied to the session's
+/// browser
+pub fn launch_config_applied(session: &str, config: &Value) -> bool {
+    fs::read_to_string(get_launch_config_path(session))
+        .is_ok_and(|saved| saved.trim() == launch_config_hash(config))
+}
+
+pub fn save_launch_config(session: &str, config: &Value) {
+    let _ = fs::write(get_launch_config_path(session), launch_config_hash(config));
+}
+
+pub fn clear_launch_config(session: &str) {
+    let _ = fs::remove_file(get_launch_config_path(session));
+}
+
+/// Last known page and storage state of a session, saved after each
+/// command when `--auto-recover` is enabled
+#[derive(Deserialize, Serialize, Default)]
+pub struct RecoverySnapshot {
+    pub url: Option<String>,
+    #[serde(rename = "storageState")]
+    pub storage_state: Option<String>,
+}
+
+/// Save the session's current URL and storage state so a crashed daemon can
+/// be relaunched where it left off. Costs two extra round-trips.
+pub fn save_recovery_snapshot(session: &str) -> Result<(), DaemonError> {
+    let url_resp = send_command(json!({ "id": gen_id(), "action": "url" }), session)?;
+    let url = url_resp
+        .data
+        .as_ref()
+        .and_then(|d| d.get("url"))
+        .and_then(|v| v.as_str())
+        .filter(|u| !u.starts_with("about:"))
+        .map(String::from);
+
+    let state_path = get_recovery_state_path(session);
+    let state_resp = send_command(
+        json!({ "id": gen_id(), "action": "state_save", "path": state_path.to_string_lossy() }),
+        session,
+    )?;
+
+    write_recovery_snapshot(
+        session,
+        &RecoverySnapshot {
+            url,
+            storage_state: state_resp
+                .success
+                .then(|| state_path.to_string_lossy().to_string()),
+        },
+    )
+}
+
+fn write_recovery_snapshot(session: &str, snapshot: &RecoverySnapshot) -> Result<(), DaemonError> {
+    let json = serde_json::to_string(snapshot).map_err(|e| e.to_string())?;
+    fs::write(get_recovery_path(session), json)
+        .map_err(|e| format!("Failed to save recovery state: {}", e).into())
+}
+
+pub fn load_recovery_snapshot(session: &str) -> Option<RecoverySnapshot> {
+    let json = fs::read_to_string(get_recovery_path(session)).ok()?;
+    serde_json::from_str(&json).ok()
+}
 
-    serde_json::from_str(&response_line).map_err(|e| format!("Invalid response: {}", e))
+/// Forget the saved recovery state (e.g. after an intentional `close`)
+pub fn clear_recovery_snapshot(session: &str) {
+    let _ = fs::remove_file(get_recovery_path(session));
+    let _ = fs::remove_file(get_recovery_state_path(session));
 }
 
 #[cfg(test)]
@@ -430,7 +808,7 @@ mod tests {
 
     impl<'a> EnvGuard<'a> {
         fn new(var_names: &[&str]) -> Self {
-            let lock = ENV_MUTEX.lock().unwrap();
+            let lock = ENV_MUTEX.lock().unwrap_or_else(|e| e.into_inner());
             let vars = var_names
                 .iter()
                 .map(|&name| (name.to_string(), env::var(name).ok()))
@@ -510,4 +888,224 @@ mod tests {
             result.to_string_lossy().contains("home") || result.to_string_lossy().contains("Users")
         );
     }
+
+    fn temp_socket_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("agent-browser-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        env::set_var("AGENT_BROWSER_SOCKET_DIR", &dir);
+        dir
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_take_stale_daemon_detects_dead_process() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = temp_socket_dir("stale");
+
+        // A pid that has exited and been reaped
+        let mut child = Command::new("true").spawn().unwrap();
+        let pid = child.id();
+        child.wait().unwrap();
+
+        fs::write(dir.join("crashed.pid"), pid.to_string()).unwrap();
+        fs::write(dir.join("crashed.sock"), "").unwrap();
+
+        assert!(take_stale_daemon("crashed"));
+        assert!(!dir.join("crashed.pid").exists());
+        assert!(!dir.join("crashed.sock").exists());
+        // Stale files are cleaned up, so the crash is only reported once
+        assert!(!take_stale_daemon("crashed"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restart_daemon_after_crash_mid_command() {
+        use std::os::unix::net::UnixListener;
+
+        if Command::new("node").arg("--version").output().is_err() {
+            return;
+        }
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR", "AGENT_BROWSER_HOME"]);
+        let dir = temp_socket_dir("restart");
+
+        // A stand-in daemon that answers every command
+        let home = dir.join("home");
+        fs::create_dir_all(home.join("dist")).unwrap();
+        fs::write(
+            home.join("dist/daemon.js"),
+            r#"
+const fs = require('fs'), net = require('net'), path = require('path');
+const dir = process.env.AGENT_BROWSER_SOCKET_DIR, session = process.env.AGENT_BROWSER_SESSION;
+const sock = path.join(dir, session + '.sock');
+try { fs.unlinkSync(sock); } catch {}
+fs.writeFileSync(path.join(dir, session + '.pid'), String(process.pid));
+net.createServer((s) => s.on('data', (d) => {
+  const id = JSON.parse(d.toString().split('\n')[0]).id;
+  s.write(JSON.stringify({ id, success: true, data: { relaunched: true } }) + '\n');
+})).listen(sock);
+setTimeout(() => process.exit(0), 10000);
+"#,
+        )
+        .unwrap();
+        env::set_var("AGENT_BROWSER_HOME", &home);
+
+        // The crashing daemon: its process is gone and it closes the
+        // connection without answering
+        let mut child = Command::new("true").spawn().unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        fs::write(dir.join("crashy.pid"), dead_pid.to_string()).unwrap();
+        let listener = UnixListener::bind(dir.join("crashy.sock")).unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let _ = BufReader::new(stream).read_line(&mut String::new());
+        });
+
+        let cmd = json!({ "id": "1", "action": "url" });
+        let err = send_command(cmd.clone(), "crashy").err().unwrap();
+        assert!(matches!(err, DaemonError::Crashed { .. }), "{}", err);
+        server.join().unwrap();
+
+        let flags = crate::flags::parse_flags(&[], &crate::config::Config::default());
+        let daemon = restart_daemon("crashy", &flags).unwrap();
+        assert!(!daemon.already_running);
+        let resp = send_command(cmd, "crashy").unwrap();
+        assert_eq!(resp.data.unwrap()["relaunched"], true);
+
+        if let Ok(pid) = fs::read_to_string(dir.join("crashy.pid")) {
+            unsafe {
+                libc::kill(pid.trim().parse().unwrap(), libc::SIGTERM);
+            }
+        }
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_take_stale_daemon_no_pid_file() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = temp_socket_dir("fresh");
+
+        assert!(!take_stale_daemon("fresh"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_recovery_snapshot_roundtrip() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = temp_socket_dir("recovery");
+
+        assert!(load_recovery_snapshot("s1").is_none());
+
+        let snapshot = RecoverySnapshot {
+            url: Some("https://example.com/dashboard".to_string()),
+            storage_state: Some("/tmp/s1.state.json".to_string()),
+        };
+        write_recovery_snapshot("s1", &snapshot).unwrap();
+
+        let loaded = load_recovery_snapshot("s1").unwrap();
+        assert_eq!(loaded.url.as_deref(), Some("https://example.com/dashboard"));
+        assert_eq!(loaded.storage_state.as_deref(), Some("/tmp/s1.state.json"));
+
+        clear_recovery_snapshot("s1");
+        assert!(load_recovery_snapshot("s1").is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_daemon_error_types() {
+        let crashed = DaemonError::Crashed {
+            session: "default".to_string(),
+            detail: "connection closed without a response".to_string(),
+        };
+        assert_eq!(crashed.error_type(), "daemon_crashed");
+        assert!(crashed.to_string().contains("'default' crashed"));
+        assert!(crashed.to_string().contains("--auto-recover"));
+
+        let other: DaemonError = "Daemon failed to start".into();
+        assert_eq!(other.error_type(), "daemon_error");
+        assert_eq!(other.to_string(), "Daemon failed to start");
+    }
+
+    #[test]
+    fn test_launch_config_roundtrip() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = temp_socket_dir("launch");
+
+        let config = json!({
+            "action": "launch",
+            "headless": false,
+            "proxy": { "server": "http://proxy:3128", "username": "u", "password": "hunter2" }
+        });
+        assert!(!launch_config_applied("s1", &config));
+
+        save_launch_config("s1", &config);
+        assert!(launch_config_applied("s1", &config));
+        assert!(!launch_config_applied(
+            "s1",
+            &json!({ "action": "launch", "headless": true })
+        ));
+        // Only a fingerprint reaches the disk, never the credentials
+        let saved = fs::read_to_string(dir.join("s1.launch")).unwrap();
+        assert!(!saved.contains("hunter2"));
+        // Fixed across builds, so a CLI upgrade doesn't relaunch the browser
+        assert_eq!(
+            launch_config_hash(&json!({ "action": "launch" })),
+            "1de5e870a903b28e"
+        );
+
+        clear_launch_config("s1");
+        assert!(!launch_config_applied("s1", &config));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("pool-*", "pool-1"));
+        assert!(glob_match("pool-*", "pool-"));
+        assert!(glob_match("*-staging", "de-staging"));
+        assert!(glob_match("pool-?", "pool-7"));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("pool-?", "pool-10"));
+        assert!(!glob_match("pool-*", "default"));
+        assert!(!glob_match("default", "default2"));
+    }
+
+    #[test]
+    fn test_resolve_sessions_literal_and_pattern() {
+        let _guard = EnvGuard::new(&["AGENT_BROWSER_SOCKET_DIR"]);
+        let dir = temp_socket_dir("resolve");
+
+        // Our own pid is alive, so these look like running sessions
+        let pid = std::process::id().to_string();
+        for name in ["pool-1", "pool-2", "other"] {
+            fs::write(dir.join(format!("{}.pid", name)), &pid).unwrap();
+        }
+
+        assert_eq!(
+            resolve_sessions("pool-*, extra,pool-1"),
+            vec!["pool-1", "pool-2", "extra"]
+        );
+        assert!(resolve_sessions("nomatch-*").is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_in_flight_tracks_until_dropped() {
+        let cmd = json!({ "id": "r-test-in-flight", "action": "wait" });
+        let entry = ("s1".to_string(), "r-test-in-flight".to_string());
+
+        let guard = InFlight::track("s1", &cmd);
+        assert!(guard.is_some());
+        assert!(in_flight().contains(&entry));
+
+        drop(guard);
+        assert!(!in_flight().contains(&entry));
+    }
 }
@@ -1,11 +1,23 @@
 use std::env;
+use std::fs;
+use std::sync::LazyLock;
 
+use crate::commands::ParseError;
+use crate::proxy;
+use crate::registry;
+
+use crate::config::{
+    self, args_string, headers_json, Config, Credentials, EnvProfile, FileConfig, Viewport,
+};
+
+#[derive(Clone)]
 pub struct Flags {
     pub json: bool,
     pub full: bool,
     pub headed: bool,
     pub debug: bool,
     pub session: String,
+    pub sessions: Option<String>,
     pub headers: Option<String>,
     pub executable_path: Option<String>,
     pub cdp: Option<String>,
@@ -14,13 +26,56 @@ pub struct Flags {
     pub state: Option<String>,
     pub proxy: Option<String>,
     pub proxy_bypass: Option<String>,
+    /// PAC script file or URL, used instead of a fixed proxy
+    pub proxy_pac: Option<String>,
+    /// File holding the proxy's `user:password`
+    pub proxy_credentials_file: Option<String>,
+    /// File of proxies to rotate through, one per session
+    pub proxy_pool: Option<String>,
+    pub proxy_pool_strategy: Option<String>,
     pub args: Option<String>,
     pub user_agent: Option<String>,
     pub provider: Option<String>,
     pub ignore_https_errors: bool,
+    pub auto_recover: bool,
+    /// Selected `[env.<name>]` config profile
+    pub env: Option<String>,
+    pub base_url: Option<String>,
+    pub credentials: Option<Credentials>,
+    pub viewport: Option<Viewport>,
+    pub locale: Option<String>,
+    /// Reject unknown flags and flags missing their value
+    pub strict: bool,
+    /// Settings given on the command line or through their env var, rather
+    /// than taken from a config file
+    pub explicit: Vec<&'static str>,
 }
 
-pub fn parse_flags(args: &[String]) -> Flags {
+/// Parse global flags from `args`, falling back to `AGENT_BROWSER_*` env vars
+/// and then to the config files
+pub fn parse_flags(args: &[String], config: &Config) -> Flags {
+    let args = before_terminator(args);
+    // The profile sits between env vars and the rest of the config file
+    let env_name = args
+        .iter()
+        .position(|a| a == "--env")
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| env::var("AGENT_BROWSER_ENV").ok());
+    let no_profile = EnvProfile::default();
+    let profile = env_name
+        .as_deref()
+        .and_then(|name| config.profile(name))
+        .map_or(&no_profile, |(p, _)| p);
+
+    let env_or_config = |var: &str, get: fn(&FileConfig) -> Option<String>| {
+        env::var(var).ok().or_else(|| config.get(get))
+    };
+    let headers: Vec<&toml::Table> = config
+        .get(|c| c.headers.as_ref())
+        .into_iter()
+        .chain(profile.headers.as_ref())
+        .collect();
+
     let extensions_env = env::var("AGENT_BROWSER_EXTENSIONS")
         .ok()
         .map(|s| {
@@ -29,26 +84,57 @@ pub fn parse_flags(args: &[String]) -> Flags {
                 .filter(|p| !p.is_empty())
                 .collect::<Vec<_>>()
         })
+        .or_else(|| config.get(|c| c.extensions.clone()))
         .unwrap_or_default();
 
     let mut flags = Flags {
         json: false,
         full: false,
-        headed: false,
+        headed: config.get(|c| c.headed).unwrap_or(false),
         debug: false,
-        session: env::var("AGENT_BROWSER_SESSION").unwrap_or_else(|_| "default".to_string()),
-        headers: None,
-        executable_path: env::var("AGENT_BROWSER_EXECUTABLE_PATH").ok(),
+        session: env_or_config("AGENT_BROWSER_SESSION", |c| c.session.clone())
+            .unwrap_or_else(|| "default".to_string()),
+        sessions: None,
+        headers: headers_json(&headers),
+        executable_path: env_or_config("AGENT_BROWSER_EXECUTABLE_PATH", |c| {
+            c.executable_path.clone()
+        }),
         cdp: None,
         extensions: extensions_env,
-        profile: env::var("AGENT_BROWSER_PROFILE").ok(),
-        state: env::var("AGENT_BROWSER_STATE").ok(),
-        proxy: env::var("AGENT_BROWSER_PROXY").ok(),
-        proxy_bypass: env::var("AGENT_BROWSER_PROXY_BYPASS").ok(),
-        args: env::var("AGENT_BROWSER_ARGS").ok(),
-        user_agent: env::var("AGENT_BROWSER_USER_AGENT").ok(),
+        profile: env_or_config("AGENT_BROWSER_PROFILE", |c| c.profile.clone()),
+        state: env::var("AGENT_BROWSER_STATE")
+            .ok()
+            .or_else(|| profile.state.clone())
+            .or_else(|| config.get(|c| c.state.clone())),
+        proxy: env::var("AGENT_BROWSER_PROXY")
+            .ok()
+            .or_else(|| profile.proxy.clone())
+            .or_else(|| config.get(|c| c.proxy.clone())),
+        proxy_bypass: env_or_config("AGENT_BROWSER_PROXY_BYPASS", |c| c.proxy_bypass.clone()),
+        proxy_pac: env_or_config("AGENT_BROWSER_PROXY_PAC", |c| c.proxy_pac.clone()),
+        proxy_credentials_file: env_or_config("AGENT_BROWSER_PROXY_CREDENTIALS_FILE", |c| {
+            c.proxy_credentials_file.clone()
+        }),
+        proxy_pool: env_or_config("AGENT_BROWSER_PROXY_POOL", |c| c.proxy_pool.clone()),
+        proxy_pool_strategy: env_or_config("AGENT_BROWSER_PROXY_POOL_STRATEGY", |c| {
+            c.proxy_pool_strategy.clone()
+        }),
+        args: env_or_config("AGENT_BROWSER_ARGS", |c| c.args.as_deref().map(args_string)),
+        user_agent: env_or_config("AGENT_BROWSER_USER_AGENT", |c| c.user_agent.clone()),
         provider: env::var("AGENT_BROWSER_PROVIDER").ok(),
-        ignore_https_errors: false,
+        ignore_https_errors: config.get(|c| c.ignore_https_errors).unwrap_or(false),
+        auto_recover: false,
+        env: env_name.clone(),
+        base_url: profile.base_url.clone(),
+        credentials: profile.credentials.clone(),
+        viewport: profile.viewport,
+        locale: profile.locale.clone(),
+        strict: env::var("AGENT_BROWSER_STRICT")
+            .ok()
+            .map(|v| !matches!(v.as_str(), "0" | "false" | "off"))
+            .or_else(|| config.get(|c| c.strict))
+            .unwrap_or(true),
+        explicit: config::explicit_flags(args),
     };
 
     let mut i = 0;
@@ -64,6 +150,16 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
+            "--env" => {
+                // Already applied above
+                i += 1;
+            }
+            "--sessions" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.sessions = Some(s.clone());
+                    i += 1;
+                }
+            }
             "--headers" => {
                 if let Some(h) = args.get(i + 1) {
                     flags.headers = Some(h.clone());
@@ -112,6 +208,30 @@ pub fn parse_flags(args: &[String]) -> Flags {
                     i += 1;
                 }
             }
+            "--proxy-pac" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.proxy_pac = Some(s.clone());
+                    i += 1;
+                }
+            }
+            "--proxy-credentials-file" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.proxy_credentials_file = Some(s.clone());
+                    i += 1;
+                }
+            }
+            "--proxy-pool" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.proxy_pool = Some(s.clone());
+                    i += 1;
+                }
+            }
+            "--proxy-pool-strategy" => {
+                if let Some(s) = args.get(i + 1) {
+                    flags.proxy_pool_strategy = Some(s.clone());
+                    i += 1;
+                }
+            }
             "--args" => {
                 if let Some(s) = args.get(i + 1) {
                     flags.args = Some(s.clone());
@@ -131,53 +251,248 @@ pub fn parse_flags(args: &[String]) -> Flags {
                 }
             }
             "--ignore-https-errors" => flags.ignore_https_errors = true,
+            "--auto-recover" => flags.auto_recover = true,
+            "--no-strict" => flags.strict = false,
             _ => {}
         }
         i += 1;
     }
+    // The standard proxy env vars come last, and don't override a PAC file
+    // or a pool
+    if flags.proxy.is_none() && flags.proxy_pac.is_none() && flags.proxy_pool.is_none() {
+        flags.proxy = proxy::standard_proxy().map(|(_, p)| p);
+    }
+    if (flags.proxy.is_some() || flags.proxy_pool.is_some()) && flags.proxy_bypass.is_none() {
+        flags.proxy_bypass = proxy::standard_no_proxy().map(|(_, hosts)| hosts);
+    }
     flags
 }
 
+/// Global switches, stripped from command args
+pub static GLOBAL_FLAGS: LazyLock<Vec<&str>> = LazyLock::new(|| global_names(false));
+
+/// Global flags that take a value (need to skip the next arg too)
+pub static GLOBAL_FLAGS_WITH_VALUE: LazyLock<Vec<&str>> = LazyLock::new(|| global_names(true));
+
+/// Long and short names of the `registry::GLOBAL_OPTIONS` with or without a
+/// value
+fn global_names(valued: bool) -> Vec<&'static str> {
+    registry::GLOBAL_OPTIONS
+        .iter()
+        .filter(|f| f.value.is_some() == valued)
+        .flat_map(|f| std::iter::once(f.long).chain(f.short))
+        .collect()
+}
+
+/// Arguments before a `--` terminator: the only ones that may be flags
+pub fn before_terminator(args: &[String]) -> &[String] {
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    &args[..end]
+}
+
+/// Expand `@file` arguments into the arguments listed in the file, and split
+/// `--flag=value` into `--flag value`. Only words in flag position are
+/// touched: never the value of a flag that takes one, nor anything after a
+/// `--`. An `@file` is only read before the command name, where nothing but
+/// global flags goes, so refs like `@e1` and text such as `@notes.txt` are
+/// left alone.
+pub fn expand_args(args: Vec<String>) -> Result<Vec<String>, String> {
+    let mut out = Vec::new();
+    let mut pos = Position::default();
+    for arg in args {
+        match arg.strip_prefix('@').filter(|_| pos.is_leading()) {
+            Some(path) => {
+                let contents = fs::read_to_string(path)
+                    .map_err(|e| format!("Cannot read arguments file {}: {}", arg, e))?;
+                let words = split_words(&contents).map_err(|e| format!("{} in {}", e, arg))?;
+                for word in words {
+                    push_arg(&mut out, &mut pos, word)?;
+                }
+            }
+            None => push_arg(&mut out, &mut pos, arg)?,
+        }
+    }
+    Ok(out)
+}
+
+/// Where the next word falls while expanding arguments
+#[derive(Default)]
+struct Position {
+    /// After a `--`: every word is an argument
+    literal: bool,
+    /// The next word is the value of the flag before it
+    value: bool,
+    /// The command name has been seen
+    command: bool,
+}
+
+impl Position {
+    /// Before the command name, in flag position
+    fn is_leading(&self) -> bool {
+        !self.literal && !self.value && !self.command
+    }
+}
+
+fn push_arg(out: &mut Vec<String>, pos: &mut Position, arg: String) -> Result<(), String> {
+    if pos.literal || pos.value {
+        pos.value = false;
+        out.push(arg);
+        return Ok(());
+    }
+    if arg == "--" {
+        pos.literal = true;
+        out.push(arg);
+        return Ok(());
+    }
+    match arg.split_once('=') {
+        Some((flag, _))
+            if flag.len() > 2 && flag.starts_with("--") && registry::is_switch(flag) =>
+        {
+            return Err(format!(
+                "{} doesn't take a value (got {}); pass {} on its own",
+                flag, arg, flag
+            ));
+        }
+        Some((flag, value)) if flag.len() > 2 && flag.starts_with("--") => {
+            out.push(flag.to_string());
+            out.push(value.to_string());
+        }
+        _ => {
+            if arg.starts_with('-') {
+                pos.value = registry::takes_value(&arg);
+            } else {
+                pos.command = true;
+            }
+            out.push(arg);
+        }
+    }
+    Ok(())
+}
+
+/// Split an arguments file into words the way a shell would: whitespace
+/// separates words, single quotes are literal, double quotes and backslashes
+/// escape, and a line starting with `#` is a comment (a `#` elsewhere is
+/// kept, as in `#email`)
+pub fn split_words(contents: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut line_start = true;
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '#' && line_start {
+            while chars.next_if(|&c| c != '\n').is_some() {}
+            continue;
+        }
+        line_start = c == '\n' || (line_start && c.is_whitespace());
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated ' quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next_if(|&c| c == '"' || c == '\\') {
+                            Some(c) => word.push(c),
+                            None => word.push('\\'),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("Unterminated \" quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Check that every global flag that takes a value has one, rather than
+/// running out of arguments or swallowing the next flag
+pub fn check_flags(args: &[String]) -> Result<(), ParseError> {
+    let args = before_terminator(args);
+    let is_flag = |a: &String| {
+        GLOBAL_FLAGS.contains(&a.as_str()) || GLOBAL_FLAGS_WITH_VALUE.contains(&a.as_str())
+    };
+    let mut i = 0;
+    while i < args.len() {
+        if GLOBAL_FLAGS_WITH_VALUE.contains(&args[i].as_str()) {
+            if args.get(i + 1).is_none_or(is_flag) {
+                return Err(ParseError::MissingArguments {
+                    context: args[i].clone(),
+                    usage: format!("{} <value> <command> [args...]", args[i]),
+                });
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    Ok(())
+}
+
+/// The global flags in `args` (with their values): the complement of
+/// `clean_args`
+pub fn global_args(args: &[String]) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut args = before_terminator(args).iter();
+    while let Some(arg) = args.next() {
+        if GLOBAL_FLAGS_WITH_VALUE.contains(&arg.as_str()) {
+            result.push(arg.clone());
+            result.extend(args.next().cloned());
+        } else if GLOBAL_FLAGS.contains(&arg.as_str()) {
+            result.push(arg.clone());
+        }
+    }
+    result
+}
+
 pub fn clean_args(args: &[String]) -> Vec<String> {
     let mut result = Vec::new();
     let mut skip_next = false;
 
-    // Global flags that should be stripped from command args
-    const GLOBAL_FLAGS: &[&str] = &[
-        "--json",
-        "--full",
-        "--headed",
-        "--debug",
-        "--ignore-https-errors",
-    ];
-    // Global flags that take a value (need to skip the next arg too)
-    const GLOBAL_FLAGS_WITH_VALUE: &[&str] = &[
-        "--session",
-        "--headers",
-        "--executable-path",
-        "--cdp",
-        "--extension",
-        "--profile",
-        "--state",
-        "--proxy",
-        "--proxy-bypass",
-        "--args",
-        "--user-agent",
-        "-p",
-        "--provider",
-    ];
-
-    for arg in args.iter() {
+    for (i, arg) in args.iter().enumerate() {
         if skip_next {
             skip_next = false;
             continue;
         }
+        // Everything from a `--` on is the command's
+        if arg == "--" {
+            result.extend_from_slice(&args[i..]);
+            break;
+        }
         if GLOBAL_FLAGS_WITH_VALUE.contains(&arg.as_str()) {
             skip_next = true;
             continue;
         }
         // Only strip known global flags, not command-specific flags
-        if GLOBAL_FLAGS.contains(&arg.as_str()) || arg == "-f" {
+        if GLOBAL_FLAGS.contains(&arg.as_str()) {
             continue;
         }
         result.push(arg.clone());
@@ -195,7 +510,10 @@ mod tests {
 
     #[test]
     fn test_parse_headers_flag() {
-        let flags = parse_flags(&args(r#"open example.com --headers {"Auth":"token"}"#));
+        let flags = parse_flags(
+            &args(r#"open example.com --headers {"Auth":"token"}"#),
+            &Config::default(),
+        );
         assert_eq!(flags.headers, Some(r#"{"Auth":"token"}"#.to_string()));
     }
 
@@ -208,7 +526,7 @@ mod tests {
             "--headers".to_string(),
             r#"{"Authorization": "Bearer token"}"#.to_string(),
         ];
-        let flags = parse_flags(&input);
+        let flags = parse_flags(&input, &Config::default());
         assert_eq!(
             flags.headers,
             Some(r#"{"Authorization": "Bearer token"}"#.to_string())
@@ -217,7 +535,7 @@ mod tests {
 
     #[test]
     fn test_parse_no_headers_flag() {
-        let flags = parse_flags(&args("open example.com"));
+        let flags = parse_flags(&args("open example.com"), &Config::default());
         assert!(flags.headers.is_none());
     }
 
@@ -255,7 +573,7 @@ mod tests {
             "--json".to_string(),
             "--headed".to_string(),
         ];
-        let flags = parse_flags(&input);
+        let flags = parse_flags(&input, &Config::default());
         assert_eq!(flags.headers, Some(r#"{"Auth":"token"}"#.to_string()));
         assert!(flags.json);
         assert!(flags.headed);
@@ -266,15 +584,16 @@ mod tests {
 
     #[test]
     fn test_parse_executable_path_flag() {
-        let flags = parse_flags(&args(
-            "--executable-path /path/to/chromium open example.com",
-        ));
+        let flags = parse_flags(
+            &args("--executable-path /path/to/chromium open example.com"),
+            &Config::default(),
+        );
         assert_eq!(flags.executable_path, Some("/path/to/chromium".to_string()));
     }
 
     #[test]
     fn test_parse_executable_path_flag_no_value() {
-        let flags = parse_flags(&args("--executable-path"));
+        let flags = parse_flags(&args("--executable-path"), &Config::default());
         assert_eq!(flags.executable_path, None);
     }
 
@@ -296,10 +615,225 @@ mod tests {
 
     #[test]
     fn test_parse_flags_with_session_and_executable_path() {
-        let flags = parse_flags(&args(
-            "--session test --executable-path /custom/chrome open example.com",
-        ));
+        let flags = parse_flags(
+            &args("--session test --executable-path /custom/chrome open example.com"),
+            &Config::default(),
+        );
         assert_eq!(flags.session, "test");
         assert_eq!(flags.executable_path, Some("/custom/chrome".to_string()));
     }
+
+    #[test]
+    fn test_parse_sessions_flag() {
+        let input = args("--sessions pool-*,staging snapshot -i");
+        let flags = parse_flags(&input, &Config::default());
+        assert_eq!(flags.sessions, Some("pool-*,staging".to_string()));
+        assert_eq!(clean_args(&input), vec!["snapshot", "-i"]);
+    }
+
+    #[test]
+    fn test_config_file_defaults() {
+        let config = Config {
+            project: Some((
+                "agent-browser.toml".into(),
+                FileConfig {
+                    headed: Some(true),
+                    user_agent: Some("from-config".to_string()),
+                    args: Some(vec!["--window-size=1280,720".to_string()]),
+                    ignore_https_errors: Some(true),
+                    ..Default::default()
+                },
+            )),
+            user: None,
+        };
+
+        let flags = parse_flags(&args("open example.com"), &config);
+        assert!(flags.headed);
+        assert!(flags.ignore_https_errors);
+        assert_eq!(flags.args.as_deref(), Some("--window-size=1280,720"));
+
+        // CLI flags win over the config file
+        let flags = parse_flags(&args("--user-agent from-cli open example.com"), &config);
+        assert_eq!(flags.user_agent.as_deref(), Some("from-cli"));
+    }
+
+    #[test]
+    fn test_env_profile_layers_over_config() {
+        let mut profiles = std::collections::BTreeMap::new();
+        profiles.insert(
+            "staging".to_string(),
+            EnvProfile {
+                base_url: Some("https://staging.example.com".to_string()),
+                proxy: Some("http://staging-proxy:8080".to_string()),
+                locale: Some("de-DE".to_string()),
+                ..Default::default()
+            },
+        );
+        let config = Config {
+            project: Some((
+                "agent-browser.toml".into(),
+                FileConfig {
+                    proxy: Some("http://default-proxy:8080".to_string()),
+                    env: Some(profiles),
+                    ..Default::default()
+                },
+            )),
+            user: None,
+        };
+
+        let flags = parse_flags(&args("--env staging open /login"), &config);
+        assert_eq!(flags.env.as_deref(), Some("staging"));
+        assert_eq!(
+            flags.base_url.as_deref(),
+            Some("https://staging.example.com")
+        );
+        assert_eq!(flags.locale.as_deref(), Some("de-DE"));
+        assert_eq!(flags.proxy.as_deref(), Some("http://staging-proxy:8080"));
+        assert_eq!(
+            clean_args(&args("--env staging open /login")),
+            vec!["open", "/login"]
+        );
+
+        let flags = parse_flags(&args("open /login"), &config);
+        assert!(flags.base_url.is_none());
+        assert_eq!(flags.proxy.as_deref(), Some("http://default-proxy:8080"));
+    }
+
+    #[test]
+    fn test_check_flags_missing_value() {
+        assert!(check_flags(&args("--session s1 open example.com")).is_ok());
+        assert!(matches!(
+            check_flags(&args("open example.com --session")),
+            Err(ParseError::MissingArguments { .. })
+        ));
+        // The next flag isn't taken as the value
+        assert!(check_flags(&args("--session --json open example.com")).is_err());
+    }
+
+    #[test]
+    fn test_no_strict() {
+        assert!(parse_flags(&args("open example.com"), &Config::default()).strict);
+        let flags = parse_flags(&args("--no-strict open example.com"), &Config::default());
+        assert!(!flags.strict);
+        assert_eq!(
+            clean_args(&args("--no-strict open example.com")),
+            vec!["open", "example.com"]
+        );
+    }
+
+    #[test]
+    fn test_expand_flag_equals_value() {
+        let expanded =
+            expand_args(args("--session=s1 open example.com --proxy=http://p:1?a=b")).unwrap();
+        assert_eq!(
+            expanded,
+            args("--session s1 open example.com --proxy http://p:1?a=b")
+        );
+        let flags = parse_flags(&expanded, &Config::default());
+        assert_eq!(flags.session, "s1");
+    }
+
+    #[test]
+    fn test_terminator() {
+        let expanded = expand_args(args("fill #x -- --json --a=b")).unwrap();
+        assert_eq!(expanded, args("fill #x -- --json --a=b"));
+        let flags = parse_flags(&expanded, &Config::default());
+        assert!(!flags.json);
+        assert_eq!(clean_args(&expanded), args("fill #x -- --json --a=b"));
+        assert!(check_flags(&args("fill #x -- --session")).is_ok());
+    }
+
+    #[test]
+    fn test_expand_args_file() {
+        let path = env::temp_dir().join(format!("ab-args-{}", std::process::id()));
+        fs::write(
+            &path,
+            "# launch options\n--headers '{\"X-A\": \"a b\"}'\n--args=\"--no-sandbox,--mute-audio\"\n",
+        )
+        .unwrap();
+        let input = vec![
+            format!("@{}", path.display()),
+            "click".to_string(),
+            "@e1".to_string(),
+        ];
+        let expanded = expand_args(input).unwrap();
+        assert_eq!(
+            expanded,
+            vec![
+                "--headers",
+                r#"{"X-A": "a b"}"#,
+                "--args",
+                "--no-sandbox,--mute-audio",
+                "click",
+                "@e1"
+            ]
+        );
+
+        // After the command name an @ argument is text, even when it names a
+        // file
+        let at_file = format!("@{}", path.display());
+        let input = vec!["fill".to_string(), "#notes".to_string(), at_file.clone()];
+        assert_eq!(expand_args(input).unwrap()[2], at_file);
+        let input = vec![
+            "network".to_string(),
+            "block".to_string(),
+            "--domains".to_string(),
+            at_file.clone(),
+        ];
+        assert_eq!(expand_args(input).unwrap()[3], at_file);
+        // So is the value of a global flag
+        let input = vec!["--session".to_string(), at_file.clone(), "open".to_string()];
+        assert_eq!(expand_args(input).unwrap()[1], at_file);
+        let _ = fs::remove_file(&path);
+
+        assert!(expand_args(args("@nonexistent-file open example.com")).is_err());
+    }
+
+    #[test]
+    fn test_expand_leaves_flag_values_alone() {
+        let expanded = expand_args(args(
+            "--args --disable-blink-features=AutomationControlled open a",
+        ))
+        .unwrap();
+        assert_eq!(
+            expanded,
+            args("--args --disable-blink-features=AutomationControlled open a")
+        );
+        assert_eq!(
+            parse_flags(&expanded, &Config::default()).args.as_deref(),
+            Some("--disable-blink-features=AutomationControlled")
+        );
+        assert_eq!(
+            expand_args(args("--user-agent --x=1 -p --y=2 open a")).unwrap(),
+            args("--user-agent --x=1 -p --y=2 open a")
+        );
+        // A switch doesn't take the next word as its value
+        assert_eq!(
+            expand_args(args("--json --session=s1 open a")).unwrap(),
+            args("--json --session s1 open a")
+        );
+    }
+
+    #[test]
+    fn test_switch_with_value_rejected() {
+        let err = expand_args(args("--json=true open example.com")).unwrap_err();
+        assert!(err.contains("--json doesn't take a value"), "{}", err);
+        assert!(expand_args(args("snapshot --interactive=false")).is_err());
+        // Flags that take a value still split
+        assert_eq!(
+            expand_args(args("network requests --filter=api")).unwrap(),
+            args("network requests --filter api")
+        );
+        // After `--` it is just text
+        assert!(expand_args(args("fill #x -- --json=true")).is_ok());
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(
+            split_words("a 'b c' \"d \\\" e\" f\\ g # not a comment\n# comment\nh").unwrap(),
+            vec!["a", "b c", "d \" e", "f g", "#", "not", "a", "comment", "h"]
+        );
+        assert!(split_words("'open").is_err());
+    }
 }
@@ -0,0 +1,253 @@
+//! Route mocking rules, from `network route` flags or a rules file.
+//!
+//! A rules file (`network mock rules.yaml`, YAML or JSON) lists rules that
+//! are tried top to bottom, the first whose url and method match wins:
+//!
+//! ```yaml
+//! rules:
+//!   - url: "**/api/users"
+//!     method: GET
+//!     status: 500
+//!     body: { error: "boom" }
+//!   - url: "**/api/search*"
+//!     delay: 3000
+//!   - url: "**/avatar.png"
+//!     bodyFile: fixtures/avatar.png
+//!     times: 1
+//!   - url: "**/ads/**"
+//!     abort: true
+//! ```
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{self, Path, PathBuf};
+
+/// One route rule, in the shape of a rules file entry
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "camelCase")]
+pub struct Rule {
+    pub url: String,
+    pub method: Option<String>,
+    pub abort: bool,
+    pub status: Option<u16>,
+    pub headers: BTreeMap<String, String>,
+    pub content_type: Option<String>,
+    /// A string is sent as is; anything else as JSON
+    pub body: Option<serde_yaml::Value>,
+    pub body_file: Option<PathBuf>,
+    pub delay: Option<u64>,
+    pub times: Option<u32>,
+}
+
+impl Rule {
+    /// The daemon's `route` fields for this rule. A relative `bodyFile` is
+    /// resolved against `base`, since the daemon runs in another directory.
+    pub fn to_route(&self, base: &Path) -> Result<Value, String> {
+        if self.url.is_empty() {
+            return Err("Route rule is missing its url".to_string());
+        }
+        let has_response = self.status.is_some()
+            || !self.headers.is_empty()
+            || self.content_type.is_some()
+            || self.body.is_some()
+            || self.body_file.is_some();
+        if self.abort && has_response {
+            return Err(format!("Route {} can't both abort and respond", self.url));
+        }
+        if self.body.is_some() && self.body_file.is_some() {
+            return Err(format!(
+                "Route {} has both a body and a body file",
+                self.url
+            ));
+        }
+        if let Some(status) = self.status {
+            if !(100..=599).contains(&status) {
+                return Err(format!("Invalid status for {}: {}", self.url, status));
+            }
+        }
+
+        let mut route = Map::new();
+        route.insert("url".to_string(), json!(self.url));
+        if let Some(ref method) = self.method {
+            route.insert("method".to_string(), json!(method.to_uppercase()));
+        }
+        if self.abort {
+            route.insert("abort".to_string(), json!(true));
+        }
+        if let Some(delay) = self.delay {
+            route.insert("delay".to_string(), json!(delay));
+        }
+        if let Some(times) = self.times {
+            if times == 0 {
+                return Err(format!("Route {} has times = 0", self.url));
+            }
+            route.insert("times".to_string(), json!(times));
+        }
+        if has_response {
+            let mut response = Map::new();
+            if let Some(status) = self.status {
+                response.insert("status".to_string(), json!(status));
+            }
+            if !self.headers.is_empty() {
+                response.insert("headers".to_string(), json!(self.headers));
+            }
+            let mut content_type = self.content_type.clone();
+            match &self.body {
+                Some(serde_yaml::Value::String(text)) => {
+                    // A JSON document passed as text is still JSON
+                    if content_type.is_none() && is_json(text) {
+                        content_type = Some("application/json".to_string());
+                    }
+                    response.insert("body".to_string(), json!(text));
+                }
+                Some(value) => {
+                    let text = serde_json::to_string(value).map_err(|e| e.to_string())?;
+                    content_type.get_or_insert_with(|| "application/json".to_string());
+                    response.insert("body".to_string(), json!(text));
+                }
+                None => {}
+            }
+            if let Some(ref file) = self.body_file {
+                let file = path::absolute(base.join(file)).map_err(|e| e.to_string())?;
+                if !file.is_file() {
+                    return Err(format!("Body file not found: {}", file.display()));
+                }
+                response.insert("path".to_string(), json!(file.to_string_lossy()));
+            }
+            if let Some(content_type) = content_type {
+                response.insert("contentType".to_string(), json!(content_type));
+            }
+            route.insert("response".to_string(), Value::Object(response));
+        }
+        Ok(Value::Object(route))
+    }
+}
+
+/// Whether a body is a JSON object or array, and so should be sent as JSON
+pub fn is_json(text: &str) -> bool {
+    let text = text.trim_start();
+    (text.starts_with('{') || text.starts_with('[')) && serde_json::from_str::<Value>(text).is_ok()
+}
+
+/// A `--header name:value` flag as a header pair
+pub fn parse_header(header: &str) -> Result<(String, String), String> {
+    match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("Invalid header: {} (expected name:value)", header)),
+    }
+}
+
+/// The daemon's `route` fields for every rule of a rules file, in order
+pub fn load_rules(file: &Path) -> Result<Vec<Value>, String> {
+    let text = fs::read_to_string(file)
+        .map_err(|e| format!("Cannot read rules file {}: {}", file.display(), e))?;
+    let invalid = |e: serde_yaml::Error| format!("Invalid rules file {}: {}", file.display(), e);
+    // Either a list of rules or a `rules:` key holding one
+    let mut doc: serde_yaml::Value = serde_yaml::from_str(&text).map_err(invalid)?;
+    if let Some(rules) = doc.get_mut("rules") {
+        doc = std::mem::take(rules);
+    }
+    let rules: Vec<Rule> = serde_yaml::from_value(doc).map_err(invalid)?;
+    if rules.is_empty() {
+        return Err(format!("No rules in {}", file.display()));
+    }
+    let base = file.parent().unwrap_or(Path::new("."));
+    rules
+        .iter()
+        .enumerate()
+        .map(|(i, rule)| {
+            rule.to_route(base)
+                .map_err(|e| format!("{} (rule {} in {})", e, i + 1, file.display()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(name: &str, text: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ab-mock-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(name);
+        fs::write(&file, text).unwrap();
+        file
+    }
+
+    #[test]
+    fn test_load_rules() {
+        write("avatar.png", "png");
+        let file = write(
+            "rules.yaml",
+            r#"
+rules:
+  - url: "**/api/users"
+    method: get
+    status: 500
+    body: { error: boom }
+  - url: "**/api/search*"
+    delay: 3000
+  - url: "**/avatar.png"
+    bodyFile: avatar.png
+    times: 1
+  - url: "**/ads/**"
+    abort: true
+"#,
+        );
+        let routes = load_rules(&file).unwrap();
+        assert_eq!(routes.len(), 4);
+        assert_eq!(routes[0]["method"], "GET");
+        assert_eq!(routes[0]["response"]["status"], 500);
+        assert_eq!(routes[0]["response"]["body"], r#"{"error":"boom"}"#);
+        assert_eq!(routes[0]["response"]["contentType"], "application/json");
+        assert_eq!(routes[1]["delay"], 3000);
+        assert!(routes[1].get("response").is_none());
+        let body_file = routes[2]["response"]["path"].as_str().unwrap();
+        assert!(Path::new(body_file).is_absolute());
+        assert_eq!(routes[2]["times"], 1);
+        assert_eq!(routes[3]["abort"], true);
+    }
+
+    #[test]
+    fn test_load_rules_json_list() {
+        let file = write(
+            "rules.json",
+            r#"[{ "url": "**/health", "status": 503, "body": "down" }]"#,
+        );
+        let routes = load_rules(&file).unwrap();
+        assert_eq!(routes[0]["response"]["status"], 503);
+        assert_eq!(routes[0]["response"]["body"], "down");
+        assert!(routes[0]["response"].get("contentType").is_none());
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let file = write("typo.yaml", "- url: a\n  staus: 500\n");
+        assert!(load_rules(&file).is_err());
+
+        let file = write("both.yaml", "- url: a\n  abort: true\n  status: 500\n");
+        let err = load_rules(&file).unwrap_err();
+        assert!(err.contains("rule 1"), "{}", err);
+
+        let file = write("missing.yaml", "- url: a\n  bodyFile: nope.json\n");
+        assert!(load_rules(&file).is_err());
+    }
+
+    #[test]
+    fn test_parse_header() {
+        assert_eq!(
+            parse_header("Retry-After: 30").unwrap(),
+            ("Retry-After".to_string(), "30".to_string())
+        );
+        assert_eq!(
+            parse_header("x-url:http://a").unwrap().1,
+            "http://a".to_string()
+        );
+        assert!(parse_header("novalue").is_err());
+        assert!(parse_header(":x").is_err());
+    }
+}
@@ -0,0 +1,403 @@
+//! Prewarmed session pool.
+//!
+//! `daemon prewarm` starts idle sessions (node daemon + Chromium) ahead of
+//! time so an agent's first command skips the cold start. `pool acquire`
+//! leases one of them by writing a `<session>.lease` file next to its pid
+//! file, and `pool release` resets the browser and drops the lease. Only a
+//! leased session with the pool's prefix can be released, by one caller at
+//! a time.
+
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::thread;
+
+use crate::color;
+use crate::commands::{gen_id, ParseError};
+use crate::connection::{
+    clear_launch_config, clear_recovery_snapshot, get_socket_dir, list_sessions, send_command,
+    start_daemon, DaemonError,
+};
+use crate::flags::Flags;
+use crate::output::{print_daemon_error, print_parse_error};
+use crate::proxy_pool;
+
+const DEFAULT_PREFIX: &str = "pool-";
+
+fn get_lease_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.lease", session))
+}
+
+fn get_release_lock_path(session: &str) -> PathBuf {
+    get_socket_dir().join(format!("{}.releasing", session))
+}
+
+/// Held while a session is being reset; removed on drop
+struct ReleaseLock(PathBuf);
+
+impl ReleaseLock {
+    fn acquire(session: &str) -> Option<Self> {
+        Self::at(get_release_lock_path(session))
+    }
+
+    fn at(path: PathBuf) -> Option<Self> {
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .ok()
+            .map(|_| ReleaseLock(path))
+    }
+}
+
+impl Drop for ReleaseLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Value of `--name <value>` in a subcommand's arguments
+fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|a| a == name)
+        .and_then(|i| args.get(i + 1))
+        .map(|s| s.as_str())
+}
+
+pub fn run_daemon(args: &[String], flags: &Flags) {
+    const VALID: &[&str] = &["prewarm"];
+
+    match args.get(1).map(|s| s.as_str()) {
+        Some("prewarm") => {
+            let count = match flag_value(args, "--count").map(|c| c.parse::<usize>()) {
+                None => 1,
+                Some(Ok(n)) if n > 0 => n,
+                Some(_) => {
+                    print_parse_error(
+                        &ParseError::InvalidValue {
+                            message: "Invalid --count: must be a positive number".to_string(),
+                            usage: "daemon prewarm [--count <n>] [--prefix <prefix>]".to_string(),
+                        },
+                        flags.json,
+                    );
+                    exit(1);
+                }
+            };
+            let prefix = flag_value(args, "--prefix").unwrap_or(DEFAULT_PREFIX);
+            prewarm(count, prefix, flags);
+        }
+        Some(sub) => {
+            print_parse_error(
+                &ParseError::UnknownSubcommand {
+                    subcommand: sub.to_string(),
+                    valid_options: VALID.to_vec(),
+                },
+                flags.json,
+            );
+            exit(1);
+        }
+        None => {
+            print_parse_error(
+                &ParseError::MissingArguments {
+                    context: "daemon".to_string(),
+                    usage: "daemon prewarm [--count <n>] [--prefix <prefix>]".to_string(),
+                },
+                flags.json,
+            );
+            exit(1);
+        }
+    }
+}
+
+pub fn run_pool(args: &[String], flags: &Flags) {
+    const VALID: &[&str] = &["acquire", "release"];
+
+    match args.get(1).map(|s| s.as_str()) {
+        Some("acquire") => {
+            let prefix = flag_value(args, "--prefix").unwrap_or(DEFAULT_PREFIX);
+            match acquire(prefix) {
+                Some(session) => {
+                    if flags.json {
+                        println!(
+                            "{}",
+                            json!({ "success": true, "data": { "session": session } })
+                        );
+                    } else {
+                        // Bare name so `SESSION=$(agent-browser pool acquire)` works
+                        println!("{}", session);
+                    }
+                }
+                None => {
+                    let e = DaemonError::Other(format!(
+                        "No idle sessions with prefix '{}'. Start more with: agent-browser daemon prewarm --count <n> --prefix {}",
+                        prefix, prefix
+                    ));
+                    print_daemon_error(&e, flags.json);
+                    exit(1);
+                }
+            }
+        }
+        Some("release") => {
+            let Some(session) = args.get(2) else {
+                print_parse_error(
+                    &ParseError::MissingArguments {
+                        context: "pool release".to_string(),
+                        usage: "pool release <session> [--prefix <prefix>]".to_string(),
+                    },
+                    flags.json,
+                );
+                exit(1);
+            };
+            let prefix = flag_value(args, "--prefix").unwrap_or(DEFAULT_PREFIX);
+            if let Err(e) = release(session, prefix) {
+                print_daemon_error(&e, flags.json);
+                exit(1);
+            }
+            if flags.json {
+                println!(
+                    "{}",
+                    json!({ "success": true, "data": { "released": session } })
+                );
+            } else {
+                println!("{} Released {}", color::success_indicator(), session);
+            }
+        }
+        Some(sub) => {
+            print_parse_error(
+                &ParseError::UnknownSubcommand {
+                    subcommand: sub.to_string(),
+                    valid_options: VALID.to_vec(),
+                },
+                flags.json,
+            );
+            exit(1);
+        }
+        None => {
+            print_parse_error(
+                &ParseError::MissingArguments {
+                    context: "pool".to_string(),
+                    usage: "pool <acquire|release> [args...]".to_string(),
+                },
+                flags.json,
+            );
+            exit(1);
+        }
+    }
+}
+
+/// Next `count` session names with `prefix` that aren't already running
+fn next_pool_names(prefix: &str, count: usize, running: &[String]) -> Vec<String> {
+    (1..)
+        .map(|i| format!("{}{}", prefix, i))
+        .filter(|name| !running.contains(name))
+        .take(count)
+        .collect()
+}
+
+fn prewarm(count: usize, prefix: &str, flags: &Flags) {
+    let names = next_pool_names(prefix, count, &list_sessions());
+
+    // Start the daemons in parallel; each one spends most of its time waiting
+    // for node to come up and Chromium to launch
+    let results: Vec<(String, Result<(), DaemonError>)> = thread::scope(|scope| {
+        let handles: Vec<_> = names
+            .iter()
+            .map(|name| scope.spawn(move || (name.clone(), warm_session(name, flags))))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("prewarm thread panicked"))
+            .collect()
+    });
+
+    let started: Vec<&str> = results
+        .iter()
+        .filter(|(_, r)| r.is_ok())
+        .map(|(name, _)| name.as_str())
+        .collect();
+    let failed: Vec<Value> = results
+        .iter()
+        .filter_map(|(name, r)| {
+            r.as_ref()
+                .err()
+                .map(|e| json!({ "session": name, "error": e.to_string() }))
+        })
+        .collect();
+
+    if flags.json {
+        println!(
+            "{}",
+            json!({
+                "success": failed.is_empty(),
+                "data": { "sessions": started, "failed": failed }
+            })
+        );
+    } else {
+        if !started.is_empty() {
+            println!(
+                "{} Prewarmed {} session{}: {}",
+                color::success_indicator(),
+                started.len(),
+                if started.len() == 1 { "" } else { "s" },
+                started.join(", ")
+            );
+        }
+        for (name, result) in &results {
+            if let Err(e) = result {
+                eprintln!("{} {}: {}", color::error_indicator(), name, e);
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        exit(1);
+    }
+}
+
+fn warm_session(session: &str, flags: &Flags) -> Result<(), DaemonError> {
+    let _ = fs::remove_file(get_lease_path(session));
+    let mut flags = flags.clone();
+    flags.session = session.to_string();
+    proxy_pool::assign(&mut flags)?;
+    start_daemon(session, &flags)?;
+    // Any non-launch command makes the daemon launch Chromium with the
+    // options it was spawned with
+    let resp = send_command(json!({ "id": gen_id(), "action": "url" }), session)?;
+    if resp.success {
+        Ok(())
+    } else {
+        Err(resp
+            .error
+            .unwrap_or_else(|| "Browser launch failed".to_string())
+            .into())
+    }
+}
+
+/// Lease the first idle running session with `prefix`. The lease file is
+/// created atomically, so concurrent acquirers never get the same session.
+fn acquire(prefix: &str) -> Option<String> {
+    list_sessions()
+        .into_iter()
+        .filter(|s| s.starts_with(prefix))
+        .find(|s| {
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(get_lease_path(s))
+                .is_ok()
+        })
+}
+
+/// Reset a leased session to a blank browser and return it to the pool
+fn release(session: &str, prefix: &str) -> Result<(), DaemonError> {
+    check_releasable(session, prefix, &get_lease_path(session))?;
+    if !list_sessions().iter().any(|s| s == session) {
+        return Err(format!("Session '{}' is not running", session).into());
+    }
+    let Some(_lock) = ReleaseLock::acquire(session) else {
+        return Err(format!("Session '{}' is already being released", session).into());
+    };
+
+    // Closing the browser drops every tab, cookie, origin's storage and
+    // cache; navigating launches a fresh one with the daemon's options
+    send_checked(
+        json!({ "id": gen_id(), "action": "context_reset" }),
+        session,
+    )?;
+    send_checked(
+        json!({ "id": gen_id(), "action": "navigate", "url": "about:blank" }),
+        session,
+    )?;
+    clear_launch_config(session);
+    clear_recovery_snapshot(session);
+
+    let _ = fs::remove_file(get_lease_path(session));
+    Ok(())
+}
+
+/// A session can be released only if it's a pool session that was leased
+fn check_releasable(session: &str, prefix: &str, lease: &Path) -> Result<(), String> {
+    if !session.starts_with(prefix) {
+        return Err(format!(
+            "Session '{}' is not in the pool with prefix '{}'",
+            session, prefix
+        ));
+    }
+    if !lease.exists() {
+        return Err(format!(
+            "Session '{}' is not leased; acquire it with: agent-browser pool acquire --prefix {}",
+            session, prefix
+        ));
+    }
+    Ok(())
+}
+
+fn send_checked(cmd: Value, session: &str) -> Result<(), DaemonError> {
+    let action = cmd["action"].as_str().unwrap_or("").to_string();
+    let resp = send_command(cmd, session)?;
+    if resp.success {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            action,
+            resp.error.unwrap_or_else(|| "Unknown error".to_string())
+        )
+        .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_pool_names_from_one() {
+        assert_eq!(
+            next_pool_names("pool-", 3, &[]),
+            vec!["pool-1", "pool-2", "pool-3"]
+        );
+    }
+
+    #[test]
+    fn test_next_pool_names_skips_running() {
+        let running = vec!["pool-1".to_string(), "pool-3".to_string()];
+        assert_eq!(
+            next_pool_names("pool-", 2, &running),
+            vec!["pool-2", "pool-4"]
+        );
+    }
+
+    #[test]
+    fn test_release_requires_a_lease() {
+        let dir = std::env::temp_dir().join(format!("ab-pool-release-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let lease = dir.join("pool-1.lease");
+
+        let err = check_releasable("work", "pool-", &lease).unwrap_err();
+        assert!(err.contains("not in the pool"), "{}", err);
+        let err = check_releasable("pool-1", "pool-", &lease).unwrap_err();
+        assert!(err.contains("not leased"), "{}", err);
+        fs::write(&lease, "").unwrap();
+        assert!(check_releasable("pool-1", "pool-", &lease).is_ok());
+
+        let path = dir.join("pool-1.releasing");
+        let lock = ReleaseLock::at(path.clone()).unwrap();
+        assert!(ReleaseLock::at(path.clone()).is_none());
+        drop(lock);
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_flag_value() {
+        let args: Vec<String> = ["daemon", "prewarm", "--count", "4", "--prefix", "w-"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(flag_value(&args, "--count"), Some("4"));
+        assert_eq!(flag_value(&args, "--prefix"), Some("w-"));
+        assert_eq!(flag_value(&args, "--missing"), None);
+    }
+}
@@ -0,0 +1,269 @@
+//! Machine-readable command schema.
+//!
+//! `schema [--format json-schema|openai-tools|anthropic-tools]` prints every
+//! registered command as a typed tool definition, so agents driving the CLI
+//! get argument names, types and allowed values from the same registry the
+//! parser uses instead of guessing them from prose.
+
+use serde_json::{json, Map, Value};
+use std::process::exit;
+
+use crate::flags::Flags;
+use crate::output::print_parse_error;
+use crate::registry::{self, Arg, ArgType, Command, SETUP};
+
+/// One command as a tool: its path and a JSON Schema for its input
+struct Tool {
+    path: String,
+    cmd: &'static Command,
+}
+
+impl Tool {
+    /// Tool name usable by every provider (`[a-zA-Z0-9_-]`)
+    fn name(&self) -> String {
+        self.path.replace([' ', '-'], "_")
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "{}. Usage: agent-browser {}",
+            self.cmd.summary,
+            registry::usage(self.cmd, &self.path)
+        )
+    }
+
+    fn parameters(&self) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for a in self.cmd.args {
+            let description = a.ty.hint().unwrap_or(a.name).to_string();
+            properties.insert(property_name(a.name), arg_schema(a, description));
+            if a.required {
+                required.push(property_name(a.name));
+            }
+        }
+        for flag in inherited_flags(&self.path) {
+            let name = flag.long.trim_start_matches('-').to_string();
+            let schema = match &flag.value {
+                Some(value) => match value.ty.hint() {
+                    Some(hint) => arg_schema(value, format!("{} ({})", flag.help, hint)),
+                    None => arg_schema(value, flag.help.to_string()),
+                },
+                None => json!({ "type": "boolean", "description": flag.help }),
+            };
+            properties.insert(name, schema);
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+}
+
+/// `port|url` -> `port_or_url`
+fn property_name(name: &str) -> String {
+    name.replace('|', "_or_")
+}
+
+/// Flags of the command at `path` and of every command above it
+fn inherited_flags(path: &str) -> Vec<&'static registry::Flag> {
+    let mut flags = Vec::new();
+    let mut prefix = String::new();
+    for name in path.split(' ') {
+        if !prefix.is_empty() {
+            prefix.push(' ');
+        }
+        prefix.push_str(name);
+        if let Some(cmd) = registry::lookup(&prefix) {
+            flags.extend(cmd.flags.iter());
+        }
+    }
+    flags
+}
+
+fn arg_schema(a: &Arg, description: String) -> Value {
+    let mut schema = match a.ty {
+        ArgType::Int => json!({ "type": "integer" }),
+        ArgType::Number => json!({ "type": "number" }),
+        ArgType::Json => json!({ "type": "string", "contentMediaType": "application/json" }),
+        ArgType::Choice(options) => json!({ "type": "string", "enum": options }),
+        _ => json!({ "type": "string" }),
+    };
+    schema["description"] = json!(description);
+    // Variadic text is joined with spaces by the CLI, so one string is
+    // equivalent; other repeated values are a list
+    if a.variadic && !matches!(a.ty, ArgType::Text | ArgType::Selector) {
+        let description = schema["description"].take();
+        schema = json!({ "type": "array", "items": schema, "description": description });
+    }
+    schema
+}
+
+/// Every command an agent can run, depth first. Groups that only dispatch
+/// to subcommands are skipped; `bare` groups and groups that also take an
+/// argument (`tab 2`, `frame main`) are tools of their own.
+fn tools() -> Vec<Tool> {
+    fn walk(cmd: &'static Command, path: String, out: &mut Vec<Tool>) {
+        if cmd.subcommands.is_empty() || cmd.bare || !cmd.args.is_empty() {
+            out.push(Tool {
+                path: path.clone(),
+                cmd,
+            });
+        }
+        for sub in cmd.subcommands {
+            walk(sub, format!("{} {}", path, sub.name), out);
+        }
+    }
+
+    let mut out = Vec::new();
+    // Setup commands (install, completions, schema) aren't for agents
+    for cmd in registry::COMMANDS.iter().filter(|c| c.group != SETUP) {
+        walk(cmd, cmd.name.to_string(), &mut out);
+    }
+    out
+}
+
+/// The schema in one of `json-schema`, `openai-tools`, `anthropic-tools`
+pub fn generate(format: &str) -> Value {
+    let tools = tools();
+    match format {
+        "openai-tools" => tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": t.name(),
+                        "description": t.description(),
+                        "parameters": t.parameters(),
+                    }
+                })
+            })
+            .collect(),
+        "anthropic-tools" => tools
+            .iter()
+            .map(|t| {
+                json!({
+                    "name": t.name(),
+                    "description": t.description(),
+                    "input_schema": t.parameters(),
+                })
+            })
+            .collect(),
+        _ => {
+            let commands: Map<String, Value> = tools
+                .iter()
+                .map(|t| {
+                    let mut schema = t.parameters();
+                    schema["description"] = json!(t.description());
+                    (t.path.clone(), schema)
+                })
+                .collect();
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "title": "agent-browser commands",
+                "version": env!("CARGO_PKG_VERSION"),
+                "commands": commands,
+            })
+        }
+    }
+}
+
+/// `schema [--format <format>]`: print the command schema as JSON
+pub fn run_schema(args: &[String], flags: &Flags) {
+    let inv = match registry::parse(args, flags.strict) {
+        Ok(inv) => inv,
+        Err(e) => {
+            print_parse_error(&e, flags.json);
+            exit(1);
+        }
+    };
+    let format = inv.value("--format").unwrap_or("json-schema");
+    let schema = generate(format);
+    // Pretty-printed unless --json asks for one line
+    if flags.json {
+        println!("{}", schema);
+    } else {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema).unwrap_or_default()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_tool_has_valid_name_and_parameters() {
+        let tools = generate("anthropic-tools");
+        let tools = tools.as_array().unwrap();
+        assert!(tools.len() > 100);
+        for tool in tools {
+            let name = tool["name"].as_str().unwrap();
+            assert!(
+                name.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+                "{}",
+                name
+            );
+            assert_eq!(tool["input_schema"]["type"], "object");
+        }
+        let names: std::collections::HashSet<_> =
+            tools.iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(names.len(), tools.len(), "duplicate tool names");
+        assert!(!names.contains("completions"));
+    }
+
+    #[test]
+    fn test_parameters_cover_args_and_flags() {
+        for tool in tools() {
+            let schema = tool.parameters();
+            let expected = tool.cmd.args.len() + inherited_flags(&tool.path).len();
+            assert_eq!(
+                schema["properties"].as_object().unwrap().len(),
+                expected,
+                "property name collision in {}",
+                tool.path
+            );
+        }
+    }
+
+    #[test]
+    fn test_enums_and_types() {
+        let schema = generate("json-schema");
+        let commands = &schema["commands"];
+
+        let cookies = &commands["cookies set"];
+        assert_eq!(
+            cookies["properties"]["sameSite"]["enum"],
+            json!(["Strict", "Lax", "None"])
+        );
+        assert_eq!(cookies["properties"]["httpOnly"]["type"], "boolean");
+        assert_eq!(cookies["properties"]["expires"]["type"], "integer");
+        assert_eq!(cookies["required"], json!(["name", "value"]));
+
+        let media = &commands["set media"]["properties"]["feature"];
+        assert_eq!(media["type"], "array");
+        assert_eq!(
+            media["items"]["enum"],
+            json!(["dark", "light", "reduced-motion"])
+        );
+
+        assert_eq!(commands["fill"]["properties"]["text"]["type"], "string");
+        assert!(commands["get text"]["description"]
+            .as_str()
+            .unwrap()
+            .contains("agent-browser get text <selector>"));
+
+        let openai = generate("openai-tools");
+        assert!(openai
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|t| t["function"]["name"] == "storage_local_set"));
+    }
+}
@@ -0,0 +1,271 @@
+//! Secret references in arguments.
+//!
+//! Arguments that usually carry secrets may be given as a reference instead
+//! of the value itself, so the secret never appears in argv, shell history or
+//! an agent's transcript:
+//!
+//! - `env:NAME` - the value of environment variable `NAME`
+//! - `file:/path` - the contents of a file (one trailing newline dropped)
+//! - `stdin:` - everything read from stdin (one trailing newline dropped)
+//!
+//! References are only resolved in the value slots the registry marks as
+//! secret (`fill`/`type` text, `find ... fill` values, `set credentials`,
+//! `cookies set` values, `set headers`) and in `--headers`; anywhere else
+//! `env:prod` is just text. A literal value that looks like a reference is
+//! escaped with a backslash (`\env:prod`).
+//!
+//! Resolved values are remembered and redacted from anything printed.
+
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::sync::Mutex;
+
+use crate::connection::Response;
+use crate::registry::{self, ArgType, Invocation};
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Every secret resolved by this process
+static RESOLVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Whether `arg` is a secret reference (rather than a value to use as given)
+pub fn is_reference(arg: &str) -> bool {
+    arg.starts_with("env:") || arg.starts_with("file:") || arg == "stdin:"
+}
+
+/// Resolve `arg` if it is a secret reference, or return it unchanged (minus
+/// the backslash escaping a literal that looks like one)
+pub fn resolve(arg: &str) -> Result<String, String> {
+    if let Some(literal) = arg.strip_prefix('\\').filter(|a| is_reference(a)) {
+        return Ok(literal.to_string());
+    }
+    let value = if let Some(name) = arg.strip_prefix("env:") {
+        env::var(name).map_err(|_| format!("Secret {} is not set", arg))?
+    } else if let Some(path) = arg.strip_prefix("file:") {
+        let contents =
+            fs::read_to_string(path).map_err(|e| format!("Cannot read secret {}: {}", arg, e))?;
+        trim_newline(contents)
+    } else if arg == "stdin:" {
+        let mut contents = String::new();
+        std::io::stdin()
+            .read_to_string(&mut contents)
+            .map_err(|e| format!("Cannot read secret from stdin: {}", e))?;
+        trim_newline(contents)
+    } else {
+        return Ok(arg.to_string());
+    };
+
+    if !value.is_empty() {
+        RESOLVED
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(value.clone());
+    }
+    Ok(value)
+}
+
+fn trim_newline(mut s: String) -> String {
+    if s.ends_with('\n') {
+        s.pop();
+        if s.ends_with('\r') {
+            s.pop();
+        }
+    }
+    s
+}
+
+/// Resolve secret references in the slots of a parsed command that the
+/// registry marks as secret
+pub fn resolve_invocation(inv: &mut Invocation) -> Result<(), String> {
+    let Some(cmd) = registry::lookup(&inv.path.join(" ")) else {
+        return Ok(());
+    };
+    for (i, a) in cmd.args.iter().enumerate() {
+        // Only a single-word variadic value can be a reference; multi-word
+        // text is typed as given
+        let single = !a.variadic || inv.args.len() == i + 1;
+        if !a.secret || !single {
+            continue;
+        }
+        if let Some(arg) = inv.args.get_mut(i) {
+            *arg = resolve_slot(a, arg)?;
+        }
+    }
+    for (long, value) in inv.flags.iter_mut() {
+        let flag = cmd.flags.iter().find(|f| f.long == *long);
+        if let (Some(a), Some(value)) = (flag.and_then(|f| f.value).filter(|a| a.secret), value) {
+            *value = resolve_slot(&a, value)?;
+        }
+    }
+    Ok(())
+}
+
+fn resolve_slot(a: &registry::Arg, value: &str) -> Result<String, String> {
+    match a.ty {
+        ArgType::Json => resolve_headers(value),
+        _ => resolve(value),
+    }
+}
+
+/// Resolve a headers JSON argument: the whole argument may be a reference
+/// (e.g. `file:headers.json`), and so may each header value
+/// (`{"Authorization": "env:API_TOKEN"}`)
+pub fn resolve_headers(arg: &str) -> Result<String, String> {
+    let json = resolve(arg)?;
+    let Ok(Value::Object(mut headers)) = serde_json::from_str::<Value>(&json) else {
+        // Left for the command parser to report
+        return Ok(json);
+    };
+    for value in headers.values_mut() {
+        if let Value::String(s) = value {
+            *s = resolve(s)?;
+        }
+    }
+    Ok(Value::Object(headers).to_string())
+}
+
+/// Replace every resolved secret in `s`
+pub fn redact_str(s: &str) -> String {
+    let mut secrets = RESOLVED.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    // Longest first, so a secret containing another is replaced whole
+    secrets.sort_by_key(|s| std::cmp::Reverse(s.len()));
+    secrets
+        .iter()
+        .fold(s.to_string(), |acc, secret| acc.replace(secret, REDACTED))
+}
+
+/// Replace every resolved secret in the strings of a JSON value
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = redact_str(s),
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        Value::Object(map) => map.values_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// Redact a daemon response before it is printed
+pub fn redact_response(resp: &mut Response) {
+    if let Some(data) = resp.data.as_mut() {
+        redact(data);
+    }
+    if let Some(error) = resp.error.as_mut() {
+        *error = redact_str(error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    /// Parse `s` and resolve its secret slots
+    fn resolved(s: &str) -> Invocation {
+        let mut inv = registry::parse(&args(s), true).unwrap();
+        resolve_invocation(&mut inv).unwrap();
+        inv
+    }
+
+    #[test]
+    fn test_resolve_env_and_file() {
+        env::set_var("AB_TEST_SECRET_ENV", "env-secret-1");
+        assert_eq!(resolve("env:AB_TEST_SECRET_ENV").unwrap(), "env-secret-1");
+        assert!(resolve("env:AB_TEST_SECRET_MISSING")
+            .unwrap_err()
+            .contains("AB_TEST_SECRET_MISSING"));
+
+        let path = env::temp_dir().join(format!("ab-secret-{}", std::process::id()));
+        fs::write(&path, "file-secret-1\n").unwrap();
+        let arg = format!("file:{}", path.display());
+        assert_eq!(resolve(&arg).unwrap(), "file-secret-1");
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(resolve("plain text").unwrap(), "plain text");
+    }
+
+    #[test]
+    fn test_resolve_escaped_literals() {
+        env::set_var("AB_TEST_SECRET_LITERAL", "should-not-appear");
+        assert_eq!(
+            resolve("\\env:AB_TEST_SECRET_LITERAL").unwrap(),
+            "env:AB_TEST_SECRET_LITERAL"
+        );
+        assert_eq!(resolve("\\stdin:").unwrap(), "stdin:");
+        // Only a reference loses its backslash
+        assert_eq!(resolve("\\n").unwrap(), "\\n");
+        for literal in ["environment", "stdin", "profile:x"] {
+            assert_eq!(resolve(literal).unwrap(), literal);
+        }
+    }
+
+    #[test]
+    fn test_resolve_only_in_secret_slots() {
+        env::set_var("AB_TEST_SECRET_ARGS", "pw-args-1");
+
+        assert_eq!(
+            resolved("fill #password env:AB_TEST_SECRET_ARGS").args[1],
+            "pw-args-1"
+        );
+        assert_eq!(
+            resolved("find label Password fill env:AB_TEST_SECRET_ARGS --exact").args[2],
+            "pw-args-1"
+        );
+        assert_eq!(
+            resolved("set credentials admin env:AB_TEST_SECRET_ARGS").args[1],
+            "pw-args-1"
+        );
+        assert_eq!(
+            resolved("cookies set sid env:AB_TEST_SECRET_ARGS --secure").args[1],
+            "pw-args-1"
+        );
+
+        // Not a secret slot
+        let open = resolved("open env:AB_TEST_SECRET_ARGS");
+        assert_eq!(open.args[0], "env:AB_TEST_SECRET_ARGS");
+        let cookie = resolved("cookies set env:AB_TEST_SECRET_ARGS x");
+        assert_eq!(cookie.args[0], "env:AB_TEST_SECRET_ARGS");
+
+        // Multi-word text is typed literally
+        let typed = resolved("type #q env:AB_TEST_SECRET_ARGS and more");
+        assert_eq!(typed.args[1], "env:AB_TEST_SECRET_ARGS");
+    }
+
+    #[test]
+    fn test_resolve_headers_values() {
+        env::set_var("AB_TEST_SECRET_HEADER", "tok-header-1");
+        let json =
+            resolve_headers(r#"{"Authorization":"env:AB_TEST_SECRET_HEADER","X-A":"b"}"#).unwrap();
+        let headers: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(headers["Authorization"], "tok-header-1");
+        assert_eq!(headers["X-A"], "b");
+        assert_eq!(resolve_headers("not json").unwrap(), "not json");
+
+        // A reference to the whole JSON passes the type check
+        env::set_var("AB_TEST_SECRET_HEADERS", r#"{"X-Token":"t"}"#);
+        let inv = resolved("set headers env:AB_TEST_SECRET_HEADERS");
+        assert_eq!(inv.args[0], r#"{"X-Token":"t"}"#);
+    }
+
+    #[test]
+    fn test_redact() {
+        env::set_var("AB_TEST_SECRET_REDACT", "hunter2-redact");
+        resolve("env:AB_TEST_SECRET_REDACT").unwrap();
+
+        let mut value = json!({
+            "id": "r1",
+            "value": "hunter2-redact",
+            "nested": ["user:hunter2-redact@host", 3]
+        });
+        redact(&mut value);
+        assert_eq!(value["value"], REDACTED);
+        assert_eq!(value["nested"][0], format!("user:{}@host", REDACTED));
+        assert_eq!(value["nested"][1], 3);
+        assert_eq!(redact_str("no secrets here"), "no secrets here");
+    }
+}
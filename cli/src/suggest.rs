@@ -0,0 +1,119 @@
+//! "Did you mean" suggestions for misspelled commands, subcommands and flags.
+
+/// Edit distance between `a` and `b`, counting an adjacent transposition
+/// (`cilck` -> `click`) as one edit
+pub fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // d[i][j]: distance between the first i chars of a and first j of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// Candidates close enough to `word` to be what was meant, closest first
+/// (at most three). Leading dashes are ignored, so `--exat` matches
+/// `--exact`.
+pub fn suggest(word: &str, candidates: &[&'static str]) -> Vec<&'static str> {
+    let word = word.trim_start_matches('-').to_lowercase();
+    if word.is_empty() {
+        return Vec::new();
+    }
+    // One typo per four characters, and at least one, but never so many
+    // that nothing of the word is left (`x` is not a typo of `p`). Longer
+    // words may also be the start of a candidate (`--sess`).
+    let len = word.chars().count();
+    let max = (len / 4).max(1);
+    let mut close: Vec<(usize, &'static str)> = candidates
+        .iter()
+        .filter_map(|c| {
+            let candidate = c.trim_start_matches('-').to_lowercase();
+            let d = distance(&word, &candidate);
+            let close = (d <= max && d < len) || (len >= 3 && candidate.starts_with(&word));
+            close.then_some((d, *c))
+        })
+        .collect();
+    close.sort_by_key(|(d, _)| *d);
+    let mut out: Vec<&'static str> = Vec::new();
+    for (_, c) in close {
+        if !out.contains(&c) {
+            out.push(c);
+        }
+    }
+    out.truncate(3);
+    out
+}
+
+/// `Did you mean "a" or "b"?`, or nothing without suggestions
+pub fn did_you_mean(suggestions: &[&str]) -> Option<String> {
+    let quoted: Vec<String> = suggestions.iter().map(|s| format!("\"{}\"", s)).collect();
+    match quoted.as_slice() {
+        [] => None,
+        [one] => Some(format!("Did you mean {}?", one)),
+        [rest @ .., last] => Some(format!("Did you mean {} or {}?", rest.join(", "), last)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("click", "click"), 0);
+        assert_eq!(distance("clik", "click"), 1);
+        assert_eq!(distance("cilck", "click"), 1);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let commands = &[
+            "click",
+            "dblclick",
+            "check",
+            "close",
+            "screenshot",
+            "scroll",
+        ];
+        assert_eq!(suggest("clik", commands), vec!["click"]);
+        assert_eq!(suggest("CLICK", commands), vec!["click"]);
+        assert_eq!(suggest("screnshot", commands), vec!["screenshot"]);
+        assert!(suggest("xyz", commands).is_empty());
+        assert_eq!(suggest("--exat", &["--exact", "--name"]), vec!["--exact"]);
+        assert_eq!(suggest("--fulll", &["--full", "--json"]), vec!["--full"]);
+        // Too short to be a typo of anything
+        assert!(suggest("--x", &["-p", "--json"]).is_empty());
+        assert_eq!(
+            suggest("--sess", &["--session", "--sessions", "--state"]),
+            vec!["--session", "--sessions"]
+        );
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        assert_eq!(did_you_mean(&[]), None);
+        assert_eq!(did_you_mean(&["click"]).unwrap(), "Did you mean \"click\"?");
+        assert_eq!(
+            did_you_mean(&["a", "b", "c"]).unwrap(),
+            "Did you mean \"a\", \"b\" or \"c\"?"
+        );
+    }
+}
@@ -1,5 +1,8 @@
-import { describe, it, expect } from 'vitest';
-import { toAIFriendlyError } from './actions.js';
+import { EventEmitter } from 'node:events';
+import { describe, it, expect, vi, afterEach } from 'vitest';
+import { errors } from 'playwright-core';
+import { executeCommand, toAIFriendlyError } from './actions.js';
+import type { BrowserManager } from './browser.js';
 
 describe('toAIFriendlyError', () => {
   describe('element blocked by overlay', () => {
@@ -37,3 +40,77 @@ describe('toAIFriendlyError', () => {
     });
   });
 });
+
+describe('cancelled commands', () => {
+  afterEach(() => {
+    vi.useRealTimers();
+  });
+
+  function browserWith(page: object): BrowserManager {
+    return {
+      getPage: () => page,
+      getDefaultTimeout: () => 60000,
+    } as unknown as BrowserManager;
+  }
+
+  it('should answer a cancelled selector wait without waiting for its timeout', async () => {
+    vi.useFakeTimers();
+    const waitForSelector = vi.fn(
+      (_selector: string, options: { timeout: number }) =>
+        new Promise((_, reject) =>
+          setTimeout(
+            () => reject(new errors.TimeoutError(`Timeout ${options.timeout}ms exceeded`)),
+            options.timeout
+          )
+        )
+    );
+    const controller = new AbortController();
+    const result = executeCommand(
+      { id: '1', action: 'wait', selector: '#never', timeout: 60000 },
+      browserWith({ waitForSelector }),
+      controller.signal
+    );
+    await vi.advanceTimersByTimeAsync(1000);
+
+    controller.abort(new Error('Cancelled'));
+    const response = await result;
+    expect(response).toMatchObject({ success: false, error: 'Cancelled' });
+
+    // One wait with the requested timeout, not re-issued in slices
+    await vi.advanceTimersByTimeAsync(60000);
+    expect(waitForSelector).toHaveBeenCalledTimes(1);
+    expect(waitForSelector.mock.calls[0][1]).toMatchObject({ timeout: 60000 });
+  });
+
+  it('should clear the timer of a fixed wait once cancelled', async () => {
+    vi.useFakeTimers();
+    const controller = new AbortController();
+    const result = executeCommand(
+      { id: '1', action: 'wait', timeout: 60000 },
+      browserWith({}),
+      controller.signal
+    );
+    await vi.advanceTimersByTimeAsync(100);
+    expect(vi.getTimerCount()).toBe(1);
+
+    controller.abort(new Error('Cancelled'));
+    expect(await result).toMatchObject({ success: false, error: 'Cancelled' });
+    expect(vi.getTimerCount()).toBe(0);
+  });
+
+  it('should stop listening for a download once cancelled', async () => {
+    const page = new EventEmitter();
+    const controller = new AbortController();
+    const result = executeCommand(
+      { id: '1', action: 'waitfordownload' },
+      browserWith(page),
+      controller.signal
+    );
+    expect(page.listenerCount('download')).toBe(1);
+
+    controller.abort(new Error('Cancelled'));
+    expect(await result).toMatchObject({ success: false, error: 'Cancelled' });
+    expect(page.listenerCount('download')).toBe(0);
+    expect(page.listenerCount('close')).toBe(0);
+  });
+});
@@ -1,8 +1,10 @@
-import type { Page, Frame } from 'playwright-core';
-import { mkdirSync } from 'node:fs';
+import { errors } from 'playwright-core';
+import type { Page, Frame, Download, Response as PlaywrightResponse } from 'playwright-core';
+import { mkdirSync, readFileSync, writeFileSync } from 'node:fs';
 import path from 'node:path';
 import type { BrowserManager, ScreencastFrame } from './browser.js';
 import { getAppDir } from './daemon.js';
+import { isText, urlMatcher } from './har.js';
 import type {
   Command,
   Response,
@@ -34,12 +36,16 @@ import type {
   TabCloseCommand,
   WindowNewCommand,
   CookiesSetCommand,
+  ContextResetCommand,
   StorageGetCommand,
   StorageSetCommand,
   StorageClearCommand,
   DialogCommand,
   PdfCommand,
   RouteCommand,
+  MockCommand,
+  BlockCommand,
+  UnblockCommand,
   RequestsCommand,
   DownloadCommand,
   GeolocationCommand,
@@ -56,7 +62,9 @@ import type {
   StylesCommand,
   TraceStartCommand,
   TraceStopCommand,
+  HarStartCommand,
   HarStopCommand,
+  HarReplayCommand,
   StorageStateSaveCommand,
   ConsoleCommand,
   ErrorsCommand,
@@ -76,6 +84,8 @@ import type {
   AddStyleCommand,
   EmulateMediaCommand,
   OfflineCommand,
+  NetworkConditionsCommand,
+  CpuThrottleCommand,
   HeadersCommand,
   GetByAltTextCommand,
   GetByTitleCommand,
@@ -99,6 +109,8 @@ import type {
   MultiSelectCommand,
   WaitForDownloadCommand,
   ResponseBodyCommand,
+  NetworkBodyCommand,
+  FetchCommand,
   ScreencastStartCommand,
   ScreencastStopCommand,
   InputMouseCommand,
@@ -204,10 +216,16 @@ export function toAIFriendlyError(error: unknown, selector: string): Error {
 }
 
 /**
- * Execute a command and return a response
+ * Execute a command and return a response. Aborting `signal` stops any wait
+ * the command is in.
  */
-export async function executeCommand(command: Command, browser: BrowserManager): Promise<Response> {
+export async function executeCommand(
+  command: Command,
+  browser: BrowserManager,
+  signal?: AbortSignal
+): Promise<Response> {
   try {
+    signal?.throwIfAborted();
     switch (command.action) {
       case 'launch':
         return await handleLaunch(command, browser);
@@ -252,7 +270,7 @@ export async function executeCommand(command: Command, browser: BrowserManager):
       case 'evaluate':
         return await handleEvaluate(command, browser);
       case 'wait':
-        return await handleWait(command, browser);
+        return await handleWait(command, browser, signal);
       case 'scroll':
         return await handleScroll(command, browser);
       case 'select':
@@ -279,6 +297,8 @@ export async function executeCommand(command: Command, browser: BrowserManager):
         return await handleCookiesSet(command, browser);
       case 'cookies_clear':
         return await handleCookiesClear(command, browser);
+      case 'context_reset':
+        return await handleContextReset(command, browser);
       case 'storage_get':
         return await handleStorageGet(command, browser);
       case 'storage_set':
@@ -291,8 +311,14 @@ export async function executeCommand(command: Command, browser: BrowserManager):
         return await handlePdf(command, browser);
       case 'route':
         return await handleRoute(command, browser);
+      case 'mock':
+        return await handleMock(command, browser);
       case 'unroute':
         return await handleUnroute(command, browser);
+      case 'block':
+        return await handleBlock(command, browser);
+      case 'unblock':
+        return await handleUnblock(command, browser);
       case 'requests':
         return await handleRequests(command, browser);
       case 'download':
@@ -345,6 +371,8 @@ export async function executeCommand(command: Command, browser: BrowserManager):
         return await handleHarStart(command, browser);
       case 'har_stop':
         return await handleHarStop(command, browser);
+      case 'har_replay':
+        return await handleHarReplay(command, browser);
       case 'state_save':
         return await handleStateSave(command, browser);
       case 'state_load':
@@ -389,6 +417,10 @@ export async function executeCommand(command: Command, browser: BrowserManager):
         return await handleEmulateMedia(command, browser);
       case 'offline':
         return await handleOffline(command, browser);
+      case 'network_conditions':
+        return await handleNetworkConditions(command, browser);
+      case 'cpu_throttle':
+        return await handleCpuThrottle(command, browser);
       case 'headers':
         return await handleHeaders(command, browser);
       case 'pause':
@@ -402,9 +434,9 @@ export async function executeCommand(command: Command, browser: BrowserManager):
       case 'nth':
         return await handleNth(command, browser);
       case 'waitforurl':
-        return await handleWaitForUrl(command, browser);
+        return await handleWaitForUrl(command, browser, signal);
       case 'waitforloadstate':
-        return await handleWaitForLoadState(command, browser);
+        return await handleWaitForLoadState(command, browser, signal);
       case 'setcontent':
         return await handleSetContent(command, browser);
       case 'timezone':
@@ -422,7 +454,7 @@ export async function executeCommand(command: Command, browser: BrowserManager):
       case 'bringtofront':
         return await handleBringToFront(command, browser);
       case 'waitforfunction':
-        return await handleWaitForFunction(command, browser);
+        return await handleWaitForFunction(command, browser, signal);
       case 'scrollintoview':
         return await handleScrollIntoView(command, browser);
       case 'addinitscript':
@@ -436,9 +468,13 @@ export async function executeCommand(command: Command, browser: BrowserManager):
       case 'multiselect':
         return await handleMultiSelect(command, browser);
       case 'waitfordownload':
-        return await handleWaitForDownload(command, browser);
+        return await handleWaitForDownload(command, browser, signal);
       case 'responsebody':
-        return await handleResponseBody(command, browser);
+        return await handleResponseBody(command, browser, signal);
+      case 'network_body':
+        return await handleNetworkBody(command, browser);
+      case 'fetch':
+        return await handleFetch(command, browser);
       case 'screencast_start':
         return await handleScreencastStart(command, browser);
       case 'screencast_stop':
@@ -627,19 +663,117 @@ async function handleEvaluate(
   return successResponse(command.id, { result });
 }
 
-async function handleWait(command: WaitCommand, browser: BrowserManager): Promise<Response> {
+/**
+ * A promise that rejects once `signal` is aborted
+ */
+function whenAborted(signal: AbortSignal): Promise<never> {
+  return new Promise((_, reject) => {
+    if (signal.aborted) {
+      reject(signal.reason);
+    } else {
+      signal.addEventListener('abort', () => reject(signal.reason), { once: true });
+    }
+  });
+}
+
+/**
+ * Settle with a Playwright wait, or reject as soon as `signal` is aborted so
+ * a cancelled request answers right away. The wait keeps its own timeout.
+ */
+function cancellableWait<T>(wait: Promise<T>, signal?: AbortSignal): Promise<T> {
+  if (!signal) {
+    return wait;
+  }
+  // Once cancelled, nobody is waiting for the result
+  wait.catch(() => {});
+  return Promise.race([wait, whenAborted(signal)]);
+}
+
+/**
+ * Wait for a page event like `page.waitForEvent`, but stop listening as soon
+ * as `signal` is aborted
+ */
+function waitForPageEvent<T>(
+  page: Page,
+  event: 'download' | 'response',
+  predicate: (value: T) => boolean,
+  timeout: number,
+  signal?: AbortSignal
+): Promise<T> {
+  const emitter = page as unknown as {
+    on(event: string, listener: (value: T) => void): void;
+    off(event: string, listener: (value: T) => void): void;
+  };
+  return new Promise((resolve, reject) => {
+    const done = () => {
+      clearTimeout(timer);
+      emitter.off(event, onEvent);
+      page.off('close', onClose);
+      signal?.removeEventListener('abort', onAbort);
+    };
+    const onEvent = (value: T) => {
+      if (predicate(value)) {
+        done();
+        resolve(value);
+      }
+    };
+    const onClose = () => {
+      done();
+      reject(new Error('Page closed'));
+    };
+    const onAbort = () => {
+      done();
+      reject(signal!.reason);
+    };
+    const timer = setTimeout(() => {
+      done();
+      reject(
+        new errors.TimeoutError(`Timeout ${timeout}ms exceeded while waiting for event "${event}"`)
+      );
+    }, timeout);
+    emitter.on(event, onEvent);
+    page.on('close', onClose);
+    signal?.addEventListener('abort', onAbort, { once: true });
+  });
+}
+
+/**
+ * Sleep like `page.waitForTimeout`, but wake up early when `signal` is aborted
+ */
+function sleep(ms: number, signal?: AbortSignal): Promise<void> {
+  return new Promise((resolve, reject) => {
+    const onAbort = () => {
+      clearTimeout(timer);
+      reject(signal!.reason);
+    };
+    const timer = setTimeout(() => {
+      signal?.removeEventListener('abort', onAbort);
+      resolve();
+    }, ms);
+    signal?.addEventListener('abort', onAbort, { once: true });
+  });
+}
+
+async function handleWait(
+  command: WaitCommand,
+  browser: BrowserManager,
+  signal?: AbortSignal
+): Promise<Response> {
   const page = browser.getPage();
 
   if (command.selector) {
-    await page.waitForSelector(command.selector, {
-      state: command.state ?? 'visible',
-      timeout: command.timeout,
-    });
+    await cancellableWait(
+      page.waitForSelector(command.selector, {
+        state: command.state ?? 'visible',
+        timeout: command.timeout,
+      }),
+      signal
+    );
   } else if (command.timeout) {
-    await page.waitForTimeout(command.timeout);
+    await sleep(command.timeout, signal);
   } else {
     // Default: wait for load state
-    await page.waitForLoadState('load');
+    await cancellableWait(page.waitForLoadState('load'), signal);
   }
 
   return successResponse(command.id, { waited: true });
@@ -995,6 +1129,18 @@ async function handleCookiesClear(
   return successResponse(command.id, { cleared: true });
 }
 
+/**
+ * Drop every cookie, storage entry, cache and tab by closing the browser. The
+ * daemon launches a fresh one with its startup options on the next command.
+ */
+async function handleContextReset(
+  command: ContextResetCommand,
+  browser: BrowserManager
+): Promise<Response> {
+  await browser.close();
+  return successResponse(command.id, { reset: true });
+}
+
 async function handleStorageGet(
   command: StorageGetCommand,
   browser: BrowserManager
@@ -1062,13 +1208,31 @@ async function handlePdf(command: PdfCommand, browser: BrowserManager): Promise<
 // Network & Request handlers
 
 async function handleRoute(command: RouteCommand, browser: BrowserManager): Promise<Response> {
-  await browser.addRoute(command.url, {
-    response: command.response,
-    abort: command.abort,
-  });
+  await browser.addRoute(command);
   return successResponse(command.id, { routed: command.url });
 }
 
+async function handleMock(command: MockCommand, browser: BrowserManager): Promise<Response> {
+  await browser.addRoutes(command.rules);
+  return successResponse(command.id, { mocked: command.rules.length });
+}
+
+async function handleBlock(command: BlockCommand, browser: BrowserManager): Promise<Response> {
+  if (!command.types?.length && !command.domains?.length) {
+    return successResponse(command.id, { blocked: browser.getBlocked() });
+  }
+  const blocked = await browser.blockResources({
+    types: command.types,
+    domains: command.domains,
+  });
+  return successResponse(command.id, { blocked });
+}
+
+async function handleUnblock(command: UnblockCommand, browser: BrowserManager): Promise<Response> {
+  const unblocked = await browser.unblockResources();
+  return successResponse(command.id, { unblocked });
+}
+
 async function handleUnroute(
   command: Command & { action: 'unroute'; url?: string },
   browser: BrowserManager
@@ -1089,7 +1253,7 @@ async function handleRequests(
   // Start tracking if not already
   browser.startRequestTracking();
 
-  const requests = browser.getRequests(command.filter);
+  const requests = browser.getRequests(command);
   return successResponse(command.id, { requests });
 }
 
@@ -1394,24 +1558,29 @@ async function handleTraceStop(
 }
 
 async function handleHarStart(
-  command: Command & { action: 'har_start' },
+  command: HarStartCommand,
   browser: BrowserManager
 ): Promise<Response> {
-  await browser.startHarRecording();
-  browser.startRequestTracking();
-  return successResponse(command.id, { started: true });
+  browser.startHarRecording({ content: command.content, urlFilter: command.urlFilter });
+  return successResponse(command.id, { started: true, har: true });
 }
 
 async function handleHarStop(command: HarStopCommand, browser: BrowserManager): Promise<Response> {
-  // HAR recording is handled at context level
-  // For now, we save tracked requests as a simplified HAR-like format
-  const requests = browser.getRequests();
+  const requestCount = await browser.stopHarRecording(command.path);
   return successResponse(command.id, {
     path: command.path,
-    requestCount: requests.length,
+    requestCount,
   });
 }
 
+async function handleHarReplay(
+  command: HarReplayCommand,
+  browser: BrowserManager
+): Promise<Response> {
+  await browser.replayHar(command.path, { notFound: command.notFound, url: command.url });
+  return successResponse(command.id, { replaying: command.path });
+}
+
 async function handleStateSave(
   command: StorageStateSaveCommand,
   browser: BrowserManager
@@ -1639,6 +1808,26 @@ async function handleOffline(command: OfflineCommand, browser: BrowserManager):
   return successResponse(command.id, { offline: command.offline });
 }
 
+async function handleNetworkConditions(
+  command: NetworkConditionsCommand,
+  browser: BrowserManager
+): Promise<Response> {
+  await browser.setNetworkConditions(command.profile, {
+    downloadKbps: command.downloadKbps,
+    uploadKbps: command.uploadKbps,
+    latency: command.latency,
+  });
+  return successResponse(command.id, { network: command.profile });
+}
+
+async function handleCpuThrottle(
+  command: CpuThrottleCommand,
+  browser: BrowserManager
+): Promise<Response> {
+  await browser.setCpuThrottling(command.rate);
+  return successResponse(command.id, { cpuRate: command.rate });
+}
+
 async function handleHeaders(command: HeadersCommand, browser: BrowserManager): Promise<Response> {
   await browser.setExtraHeaders(command.headers);
   return successResponse(command.id, { set: true });
@@ -1736,19 +1925,21 @@ async function handleNth(command: NthCommand, browser: BrowserManager): Promise<
 
 async function handleWaitForUrl(
   command: WaitForUrlCommand,
-  browser: BrowserManager
+  browser: BrowserManager,
+  signal?: AbortSignal
 ): Promise<Response> {
   const page = browser.getPage();
-  await page.waitForURL(command.url, { timeout: command.timeout });
+  await cancellableWait(page.waitForURL(command.url, { timeout: command.timeout }), signal);
   return successResponse(command.id, { url: page.url() });
 }
 
 async function handleWaitForLoadState(
   command: WaitForLoadStateCommand,
-  browser: BrowserManager
+  browser: BrowserManager,
+  signal?: AbortSignal
 ): Promise<Response> {
   const page = browser.getPage();
-  await page.waitForLoadState(command.state, { timeout: command.timeout });
+  await cancellableWait(page.waitForLoadState(command.state, { timeout: command.timeout }), signal);
   return successResponse(command.id, { state: command.state });
 }
 
@@ -1830,10 +2021,14 @@ async function handleBringToFront(
 
 async function handleWaitForFunction(
   command: WaitForFunctionCommand,
-  browser: BrowserManager
+  browser: BrowserManager,
+  signal?: AbortSignal
 ): Promise<Response> {
   const page = browser.getPage();
-  await page.waitForFunction(command.expression, { timeout: command.timeout });
+  await cancellableWait(
+    page.waitForFunction(command.expression, undefined, { timeout: command.timeout }),
+    signal
+  );
   return successResponse(command.id, { waited: true });
 }
 
@@ -1887,10 +2082,17 @@ async function handleMultiSelect(
 
 async function handleWaitForDownload(
   command: WaitForDownloadCommand,
-  browser: BrowserManager
+  browser: BrowserManager,
+  signal?: AbortSignal
 ): Promise<Response> {
   const page = browser.getPage();
-  const download = await page.waitForEvent('download', { timeout: command.timeout });
+  const download = await waitForPageEvent<Download>(
+    page,
+    'download',
+    () => true,
+    command.timeout ?? browser.getDefaultTimeout(page),
+    signal
+  );
 
   let filePath: string;
   if (command.path) {
@@ -1909,27 +2111,83 @@ async function handleWaitForDownload(
 
 async function handleResponseBody(
   command: ResponseBodyCommand,
+  browser: BrowserManager,
+  signal?: AbortSignal
+): Promise<Response> {
+  const page = browser.getPage();
+  const matches = urlMatcher(command.url);
+  const response = await waitForPageEvent<PlaywrightResponse>(
+    page,
+    'response',
+    (resp) => matches(resp.url()),
+    command.timeout ?? browser.getDefaultTimeout(page),
+    signal
+  );
+  return successResponse(command.id, await responseBody(response, command.path));
+}
+
+async function handleNetworkBody(
+  command: NetworkBodyCommand,
   browser: BrowserManager
 ): Promise<Response> {
+  if (command.index === undefined && command.url === undefined) {
+    throw new Error('network_body needs a url pattern or a request index');
+  }
+  const response = await browser.getLoggedResponse({ index: command.index, url: command.url });
+  return successResponse(command.id, await responseBody(response, command.path));
+}
+
+async function handleFetch(command: FetchCommand, browser: BrowserManager): Promise<Response> {
   const page = browser.getPage();
-  const response = await page.waitForResponse((resp) => resp.url().includes(command.url), {
+  // A bare path is relative to the page, e.g. `fetch /api/me` after logging in
+  const url = command.url.startsWith('/') ? new URL(command.url, page.url()).href : command.url;
+  const data = command.dataFile ? readFileSync(command.dataFile) : command.data;
+  // The context's request API shares its cookies, so the call is logged in
+  const response = await page.context().request.fetch(url, {
+    method: command.method ?? (data !== undefined ? 'POST' : 'GET'),
+    headers: command.headers,
+    data,
     timeout: command.timeout,
   });
+  try {
+    const result = await responseBody(response, command.path);
+    return successResponse(command.id, {
+      ...result,
+      statusText: response.statusText(),
+      headers: response.headersArray(),
+    });
+  } finally {
+    await response.dispose();
+  }
+}
 
-  const body = await response.text();
-  let parsed: unknown = body;
+/**
+ * A response's body for the CLI: saved to `savePath`, or inline (parsed if
+ * JSON, base64 if binary)
+ */
+async function responseBody(
+  response: Pick<PlaywrightResponse, 'body' | 'url' | 'status' | 'headers'>,
+  savePath?: string
+): Promise<Record<string, unknown>> {
+  const body = await response.body();
+  const meta = { url: response.url(), status: response.status() };
+  if (savePath) {
+    mkdirSync(path.dirname(savePath), { recursive: true });
+    writeFileSync(savePath, body);
+    return { ...meta, path: savePath, size: body.length };
+  }
 
+  const mimeType = (response.headers()['content-type'] ?? '').split(';')[0].trim();
+  if (!isText(mimeType)) {
+    return { ...meta, body: body.toString('base64'), encoding: 'base64' };
+  }
+  const text = body.toString('utf8');
   try {
-    parsed = JSON.parse(body);
+    return { ...meta, body: JSON.parse(text) };
   } catch {
     // Keep as string if not JSON
+    return { ...meta, body: text };
   }
-
-  return successResponse(command.id, {
-    url: response.url(),
-    status: response.status(),
-    body: parsed,
-  });
 }
 
 // Screencast and input injection handlers
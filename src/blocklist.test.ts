@@ -0,0 +1,31 @@
+import { describe, it, expect } from 'vitest';
+import { DOMAIN_LISTS, expandDomains, matchesDomain } from './blocklist.js';
+
+describe('expandDomains', () => {
+  it('should expand bundled lists and normalize domains', () => {
+    const domains = expandDomains(['@ads', 'Tracker.Example.com', '*.cdn.example.net']);
+    expect(domains).toContain('doubleclick.net');
+    expect(domains).toContain('tracker.example.com');
+    expect(domains).toContain('cdn.example.net');
+    expect(domains.length).toBe(DOMAIN_LISTS.ads.length + 2);
+  });
+
+  it('should reject unknown lists', () => {
+    expect(() => expandDomains(['@nope'])).toThrow('Unknown domain list');
+  });
+});
+
+describe('matchesDomain', () => {
+  const domains = new Set(['doubleclick.net', 'ads.example.com']);
+
+  it('should match domains and their subdomains', () => {
+    expect(matchesDomain('doubleclick.net', domains)).toBe(true);
+    expect(matchesDomain('stats.g.doubleclick.net', domains)).toBe(true);
+    expect(matchesDomain('ads.example.com', domains)).toBe(true);
+  });
+
+  it('should not match parents or lookalikes', () => {
+    expect(matchesDomain('example.com', domains)).toBe(false);
+    expect(matchesDomain('notdoubleclick.net', domains)).toBe(false);
+  });
+});
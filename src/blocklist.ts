@@ -0,0 +1,108 @@
+/**
+ * Domain lists for `network block --domains @<name>`.
+ *
+ * Kept short on purpose: the hosts that serve most ad and analytics requests
+ * on typical pages, not a full filter list. A domain also matches its
+ * subdomains.
+ */
+
+const ADS = [
+  // Ad networks and exchanges
+  'doubleclick.net',
+  'googlesyndication.com',
+  'googleadservices.com',
+  'adservice.google.com',
+  'googletagservices.com',
+  'amazon-adsystem.com',
+  'adnxs.com',
+  'adsrvr.org',
+  'criteo.com',
+  'criteo.net',
+  'taboola.com',
+  'outbrain.com',
+  'rubiconproject.com',
+  'pubmatic.com',
+  'openx.net',
+  'casalemedia.com',
+  'moatads.com',
+  'media.net',
+  'smartadserver.com',
+  'adform.net',
+  'serving-sys.com',
+  'bidswitch.net',
+  'mathtag.com',
+  'sharethrough.com',
+  'teads.tv',
+  '33across.com',
+  'yieldmo.com',
+  'adroll.com',
+  'quantserve.com',
+  'ads-twitter.com',
+  'ads.linkedin.com',
+  'ads.yahoo.com',
+  // Analytics, tag managers and tracking pixels
+  'google-analytics.com',
+  'googletagmanager.com',
+  'analytics.google.com',
+  'connect.facebook.net',
+  'scorecardresearch.com',
+  'hotjar.com',
+  'mixpanel.com',
+  'segment.io',
+  'cdn.segment.com',
+  'fullstory.com',
+  'heapanalytics.com',
+  'amplitude.com',
+  'clarity.ms',
+  'bat.bing.com',
+  'analytics.twitter.com',
+  'snap.licdn.com',
+  'analytics.tiktok.com',
+  'chartbeat.com',
+  'chartbeat.net',
+  'demdex.net',
+  'omtrdc.net',
+  'everesttech.net',
+  'krxd.net',
+  'bluekai.com',
+  'nr-data.net',
+];
+
+export const DOMAIN_LISTS: Record<string, string[]> = {
+  ads: ADS,
+};
+
+/**
+ * Expand `@name` entries into the bundled lists and lowercase the rest
+ */
+export function expandDomains(domains: string[]): string[] {
+  const expanded = new Set<string>();
+  for (const domain of domains) {
+    if (domain.startsWith('@')) {
+      const list = DOMAIN_LISTS[domain.slice(1)];
+      if (!list) {
+        const known = Object.keys(DOMAIN_LISTS)
+          .map((name) => `@${name}`)
+          .join(', ');
+        throw new Error(`Unknown domain list: ${domain} (available: ${known})`);
+      }
+      list.forEach((d) => expanded.add(d));
+    } else {
+      expanded.add(domain.toLowerCase().replace(/^\*?\./, ''));
+    }
+  }
+  return [...expanded];
+}
+
+/**
+ * Whether `hostname` is one of `domains` or a subdomain of one
+ */
+export function matchesDomain(hostname: string, domains: Set<string>): boolean {
+  let host = hostname.toLowerCase();
+  for (;;) {
+    if (domains.has(host)) return true;
+    const dot = host.indexOf('.');
+    if (dot === -1) return false;
+    host = host.slice(dot + 1);
+  }
+}
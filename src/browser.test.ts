@@ -1,5 +1,5 @@
 import { describe, it, expect, beforeAll, afterAll, vi } from 'vitest';
-import { BrowserManager } from './browser.js';
+import { BrowserManager, statusMatcher } from './browser.js';
 import { chromium } from 'playwright-core';
 
 describe('BrowserManager', () => {
@@ -662,3 +662,20 @@ describe('BrowserManager', () => {
     });
   });
 });
+
+describe('statusMatcher', () => {
+  it('should match exact codes, classes, ranges and lists', () => {
+    expect(statusMatcher('422')(422)).toBe(true);
+    expect(statusMatcher('422')(421)).toBe(false);
+    expect(statusMatcher('4xx')(404)).toBe(true);
+    expect(statusMatcher('4XX')(500)).toBe(false);
+    expect(statusMatcher('500-503')(503)).toBe(true);
+    expect(statusMatcher('500-503')(504)).toBe(false);
+    expect(statusMatcher('401, 403')(403)).toBe(true);
+  });
+
+  it('should reject invalid patterns', () => {
+    expect(() => statusMatcher('4x')).toThrow();
+    expect(() => statusMatcher('abc')).toThrow();
+  });
+});
@@ -9,6 +9,7 @@ import {
   type Frame,
   type Dialog,
   type Request,
+  type Response,
   type Route,
   type Locator,
   type CDPSession,
@@ -17,8 +18,22 @@ import {
 import path from 'node:path';
 import os from 'node:os';
 import { existsSync, mkdirSync, rmSync } from 'node:fs';
-import type { LaunchCommand } from './types.js';
+import type { LaunchCommand, NetworkProfile, RouteRule } from './types.js';
 import { type RefMap, type EnhancedSnapshot, getEnhancedSnapshot, parseRef } from './snapshot.js';
+import { HarRecorder, urlMatcher, type HarOptions } from './har.js';
+import { expandDomains, matchesDomain } from './blocklist.js';
+
+/**
+ * Throttling presets, matching Chrome DevTools: throughput in kbps, latency
+ * in ms
+ */
+export const NETWORK_PROFILES: Record<
+  'slow-3g' | 'fast-3g',
+  { downloadKbps: number; uploadKbps: number; latency: number }
+> = {
+  'slow-3g': { downloadKbps: 400, uploadKbps: 400, latency: 2000 },
+  'fast-3g': { downloadKbps: 1440, uploadKbps: 675, latency: 563 },
+};
 
 // Screencast frame data from CDP
 export interface ScreencastFrame {
@@ -44,12 +59,52 @@ export interface ScreencastOptions {
   everyNthFrame?: number;
 }
 
-interface TrackedRequest {
+export interface TrackedRequest {
+  /** Position in the log, from 1; `network body <index>` refers to it */
+  index: number;
   url: string;
   method: string;
   headers: Record<string, string>;
   timestamp: number;
   resourceType: string;
+  /** HTTP status, once the response arrived */
+  status?: number;
+  /** Milliseconds from start to the end of the response (or failure) */
+  duration?: number;
+  /** Bytes received: response headers plus body */
+  size?: number;
+  /** Why the request failed, e.g. net::ERR_CONNECTION_REFUSED */
+  failure?: string;
+}
+
+export interface RequestFilter {
+  /** URL substring */
+  filter?: string;
+  method?: string;
+  resourceType?: string;
+  /** Status pattern: 404, 4xx, 500-599 or a comma-separated list of those */
+  status?: string;
+  /** Only requests started at or after this time (ms since epoch) */
+  since?: number;
+  /** Only requests that got no response */
+  failed?: boolean;
+}
+
+/**
+ * Predicate for a status pattern such as `422`, `4xx`, `500-599` or
+ * `401,403`
+ */
+export function statusMatcher(pattern: string): (status: number) => boolean {
+  const tests = pattern.split(',').map((part): ((status: number) => boolean) => {
+    const p = part.trim().toLowerCase();
+    const klass = p.match(/^([1-5])xx$/);
+    if (klass) return (s) => Math.floor(s / 100) === Number(klass[1]);
+    const range = p.match(/^(\d{3})-(\d{3})$/);
+    if (range) return (s) => s >= Number(range[1]) && s <= Number(range[2]);
+    if (/^\d{3}$/.test(p)) return (s) => s === Number(p);
+    throw new Error(`Invalid status pattern: ${part}`);
+  });
+  return (status) => tests.some((test) => test(status));
 }
 
 interface ConsoleMessage {
@@ -82,13 +137,30 @@ export class BrowserManager {
   private activeFrame: Frame | null = null;
   private dialogHandler: ((dialog: Dialog) => Promise<void>) | null = null;
   private trackedRequests: TrackedRequest[] = [];
-  private routes: Map<string, (route: Route) => Promise<void>> = new Map();
+  private trackedPages: WeakSet<Page> = new WeakSet();
+  /** Playwright request behind each log entry, to read its response later */
+  private trackedRequestObjects: WeakMap<TrackedRequest, Request> = new WeakMap();
+  private requestCounter: number = 0;
+  private routes: Map<string, Array<(route: Route) => Promise<void>>> = new Map();
+  /** What `network block` aborts, and the route doing it */
+  private blocked: {
+    types: Set<string>;
+    domains: Set<string>;
+    page: Page;
+    handler: (route: Route) => Promise<void>;
+  } | null = null;
+  /** Whether setNetworkConditions throttled the current CDP session */
+  private networkThrottled: boolean = false;
+  /** HAR files being replayed with routeFromHAR */
+  private harReplays: string[] = [];
   private consoleMessages: ConsoleMessage[] = [];
   private pageErrors: PageError[] = [];
-  private isRecordingHar: boolean = false;
+  private harRecorder: HarRecorder | null = null;
   private refMap: RefMap = {};
   private lastSnapshot: string = '';
   private scopedHeaderRoutes: Map<string, (route: Route) => Promise<void>> = new Map();
+  /** Default action timeout set on each context */
+  private contextTimeouts: WeakMap<BrowserContext, number> = new WeakMap();
 
   // CDP session for screencast and input injection
   private cdpSession: CDPSession | null = null;
@@ -192,6 +264,19 @@ export class BrowserManager {
     return this.pages[this.activePageIndex];
   }
 
+  /**
+   * Get the default timeout of a page's context (Playwright's own default
+   * when none was set)
+   */
+  getDefaultTimeout(page: Page = this.getPage()): number {
+    return this.contextTimeouts.get(page.context()) ?? 30000;
+  }
+
+  private setDefaultTimeout(context: BrowserContext, timeout: number): void {
+    context.setDefaultTimeout(timeout);
+    this.contextTimeouts.set(context, timeout);
+  }
+
   /**
    * Get the current frame (or page's main frame if no frame is selected)
    */
@@ -274,29 +359,61 @@ export class BrowserManager {
   }
 
   /**
-   * Start tracking requests
+   * Start tracking requests of the active page (once per page)
    */
   startRequestTracking(): void {
     const page = this.getPage();
+    if (this.trackedPages.has(page)) return;
+    this.trackedPages.add(page);
+
+    const entries = new WeakMap<Request, TrackedRequest>();
     page.on('request', (request: Request) => {
-      this.trackedRequests.push({
+      const entry: TrackedRequest = {
+        index: ++this.requestCounter,
         url: request.url(),
         method: request.method(),
         headers: request.headers(),
         timestamp: Date.now(),
         resourceType: request.resourceType(),
-      });
+      };
+      entries.set(request, entry);
+      this.trackedRequestObjects.set(entry, request);
+      this.trackedRequests.push(entry);
+    });
+    page.on('requestfinished', async (request: Request) => {
+      const entry = entries.get(request);
+      if (!entry) return;
+      const timing = request.timing();
+      entry.duration =
+        timing.responseEnd >= 0 ? Math.round(timing.responseEnd) : Date.now() - entry.timestamp;
+      const response = await request.response().catch(() => null);
+      if (response) entry.status = response.status();
+      const sizes = await request.sizes().catch(() => null);
+      if (sizes) entry.size = sizes.responseHeadersSize + sizes.responseBodySize;
+    });
+    page.on('requestfailed', (request: Request) => {
+      const entry = entries.get(request);
+      if (!entry) return;
+      entry.duration = Date.now() - entry.timestamp;
+      entry.failure = request.failure()?.errorText ?? 'failed';
     });
   }
 
   /**
    * Get tracked requests
    */
-  getRequests(filter?: string): TrackedRequest[] {
-    if (filter) {
-      return this.trackedRequests.filter((r) => r.url.includes(filter));
-    }
-    return this.trackedRequests;
+  getRequests(filter: RequestFilter = {}): TrackedRequest[] {
+    const matchesStatus = filter.status ? statusMatcher(filter.status) : null;
+    const method = filter.method?.toUpperCase();
+    return this.trackedRequests.filter(
+      (r) =>
+        (!filter.filter || r.url.includes(filter.filter)) &&
+        (!method || r.method === method) &&
+        (!filter.resourceType || r.resourceType === filter.resourceType) &&
+        (!matchesStatus || (r.status !== undefined && matchesStatus(r.status))) &&
+        (filter.since === undefined || r.timestamp >= filter.since) &&
+        (!filter.failed || r.failure !== undefined)
+    );
   }
 
   /**
@@ -304,44 +421,166 @@ export class BrowserManager {
    */
   clearRequests(): void {
     this.trackedRequests = [];
+    this.requestCounter = 0;
   }
 
   /**
-   * Add a route to intercept requests
+   * Response of a logged request: the one at `index`, or the most recent
+   * whose URL matches `url` (glob, `/regex/` or substring) and got a response
    */
-  async addRoute(
-    url: string,
-    options: {
-      response?: {
-        status?: number;
-        body?: string;
-        contentType?: string;
-        headers?: Record<string, string>;
-      };
-      abort?: boolean;
+  async getLoggedResponse(target: { index?: number; url?: string }): Promise<Response> {
+    if (target.index !== undefined) {
+      const entry = this.trackedRequests.find((r) => r.index === target.index);
+      const request = entry && this.trackedRequestObjects.get(entry);
+      if (!request) {
+        throw new Error(`No request #${target.index} in the log`);
+      }
+      const response = await request.response();
+      if (!response) {
+        throw new Error(`Request #${target.index} got no response${entry.failure ? `: ${entry.failure}` : ''}`);
+      }
+      return response;
     }
-  ): Promise<void> {
+
+    const matches = urlMatcher(target.url);
+    for (const entry of [...this.trackedRequests].reverse()) {
+      const request = this.trackedRequestObjects.get(entry);
+      if (!request || !matches(entry.url) || entry.failure) continue;
+      const response = await request.response().catch(() => null);
+      if (response) return response;
+    }
+    throw new Error(`No logged response matches ${target.url}`);
+  }
+
+  /**
+   * Add a route to intercept requests. Requests of another method, or past
+   * `times`, fall through to earlier routes or the network.
+   */
+  async addRoute(rule: RouteRule): Promise<void> {
     const page = this.getPage();
+    const { url, method, response, abort, delay, times } = rule;
+    let hits = 0;
 
     const handler = async (route: Route) => {
-      if (options.abort) {
+      if (method && route.request().method() !== method.toUpperCase()) {
+        await route.fallback();
+        return;
+      }
+      if (times !== undefined && hits >= times) {
+        await route.fallback();
+        return;
+      }
+      hits++;
+      if (times !== undefined && hits >= times) {
+        // Not awaited: unroute waits for running handlers, this one included
+        void this.dropRoute(page, url, handler);
+      }
+      if (delay) {
+        await new Promise((resolve) => setTimeout(resolve, delay));
+      }
+      if (abort) {
         await route.abort();
-      } else if (options.response) {
+      } else if (response) {
         await route.fulfill({
-          status: options.response.status ?? 200,
-          body: options.response.body ?? '',
-          contentType: options.response.contentType ?? 'text/plain',
-          headers: options.response.headers,
+          status: response.status ?? 200,
+          headers: response.headers,
+          // Playwright picks the content type of a file from its extension
+          ...(response.path
+            ? { path: response.path, contentType: response.contentType }
+            : {
+                body: response.body ?? '',
+                contentType: response.contentType ?? 'text/plain',
+              }),
         });
       } else {
         await route.continue();
       }
     };
 
-    this.routes.set(url, handler);
+    this.routes.set(url, [...(this.routes.get(url) ?? []), handler]);
     await page.route(url, handler);
   }
 
+  /**
+   * Add several routes; the first rule matching a request handles it
+   */
+  async addRoutes(rules: RouteRule[]): Promise<void> {
+    // Playwright tries the most recently added route first
+    for (const rule of [...rules].reverse()) {
+      await this.addRoute(rule);
+    }
+  }
+
+  private async dropRoute(
+    page: Page,
+    url: string,
+    handler: (route: Route) => Promise<void>
+  ): Promise<void> {
+    const remaining = (this.routes.get(url) ?? []).filter((h) => h !== handler);
+    if (remaining.length > 0) {
+      this.routes.set(url, remaining);
+    } else {
+      this.routes.delete(url);
+    }
+    await page.unroute(url, handler).catch(() => {});
+  }
+
+  /**
+   * Abort requests of the given resource types or to the given domains
+   * (`@ads` for the bundled list). Adds to what is already blocked.
+   */
+  async blockResources(options: {
+    types?: string[];
+    domains?: string[];
+  }): Promise<{ types: string[]; domains: number }> {
+    const types = new Set([...(this.blocked?.types ?? []), ...(options.types ?? [])]);
+    const domains = new Set([
+      ...(this.blocked?.domains ?? []),
+      ...expandDomains(options.domains ?? []),
+    ]);
+    await this.unblockResources();
+
+    const page = this.getPage();
+    const handler = async (route: Route) => {
+      const request = route.request();
+      let hostname = '';
+      try {
+        hostname = new URL(request.url()).hostname;
+      } catch {
+        // data: and other URLs without a host
+      }
+      if (types.has(request.resourceType()) || (hostname && matchesDomain(hostname, domains))) {
+        await route.abort('blockedbyclient');
+      } else {
+        await route.fallback();
+      }
+    };
+    await page.route('**/*', handler);
+    this.blocked = { types, domains, page, handler };
+    return this.getBlocked();
+  }
+
+  /**
+   * What blockResources currently blocks
+   */
+  getBlocked(): { types: string[]; domains: number } {
+    return {
+      types: [...(this.blocked?.types ?? [])],
+      domains: this.blocked?.domains.size ?? 0,
+    };
+  }
+
+  /**
+   * Stop blocking what blockResources blocked
+   */
+  async unblockResources(): Promise<boolean> {
+    if (!this.blocked) return false;
+    const { page, handler } = this.blocked;
+    this.blocked = null;
+    await page.unroute('**/*', handler).catch(() => {});
+    return true;
+  }
+
   /**
    * Remove a route
    */
@@ -349,20 +588,48 @@ export class BrowserManager {
     const page = this.getPage();
 
     if (url) {
-      const handler = this.routes.get(url);
-      if (handler) {
+      for (const handler of this.routes.get(url) ?? []) {
         await page.unroute(url, handler);
-        this.routes.delete(url);
       }
+      this.routes.delete(url);
     } else {
       // Remove all routes
-      for (const [routeUrl, handler] of this.routes) {
-        await page.unroute(routeUrl, handler);
+      for (const [routeUrl, handlers] of this.routes) {
+        for (const handler of handlers) {
+          await page.unroute(routeUrl, handler);
+        }
       }
       this.routes.clear();
+      // HAR replay is the only thing routed at context level
+      if (this.harReplays.length > 0) {
+        for (const context of this.contexts) {
+          await context.unrouteAll({ behavior: 'ignoreErrors' });
+        }
+        this.harReplays = [];
+      }
     }
   }
 
+  /**
+   * Serve matching requests of every context from a HAR file. Routes added
+   * with addRoute (page level) still take precedence.
+   */
+  async replayHar(
+    harPath: string,
+    options: { notFound?: 'abort' | 'fallback'; url?: string }
+  ): Promise<void> {
+    if (this.contexts.length === 0) {
+      throw new Error('Browser not launched. Call launch first.');
+    }
+    for (const context of this.contexts) {
+      await context.routeFromHAR(harPath, {
+        notFound: options.notFound ?? 'abort',
+        url: options.url,
+      });
+    }
+    this.harReplays.push(harPath);
+  }
+
   /**
    * Set geolocation
    */
@@ -499,18 +766,35 @@ export class BrowserManager {
   }
 
   /**
-   * Start HAR recording
+   * Start HAR recording of every context's traffic
    */
-  async startHarRecording(): Promise<void> {
-    // HAR is started at context level, flag for tracking
-    this.isRecordingHar = true;
+  startHarRecording(options: HarOptions = {}): void {
+    if (this.harRecorder) {
+      throw new Error('HAR recording already in progress. Run har stop <path> first.');
+    }
+    this.harRecorder = new HarRecorder(this.contexts, options);
+  }
+
+  /**
+   * Stop HAR recording and write the HAR file; returns the entry count
+   */
+  async stopHarRecording(harPath: string): Promise<number> {
+    const recorder = this.harRecorder;
+    if (!recorder) {
+      throw new Error('No HAR recording in progress. Run har start first.');
+    }
+    this.harRecorder = null;
+    const browser = this.browser
+      ? { name: this.browser.browserType().name(), version: this.browser.version() }
+      : undefined;
+    return recorder.save(harPath, browser);
   }
 
   /**
    * Check if HAR recording
    */
   isHarRecording(): boolean {
-    return this.isRecordingHar;
+    return this.harRecorder !== null;
   }
 
   /**
@@ -523,6 +807,44 @@ export class BrowserManager {
     }
   }
 
+  /**
+   * Emulate a network profile. `offline` works in every browser; throttling
+   * uses CDP, so needs Chromium, and applies to the current tab.
+   */
+  async setNetworkConditions(
+    profile: NetworkProfile,
+    custom: { downloadKbps?: number; uploadKbps?: number; latency?: number } = {}
+  ): Promise<void> {
+    await this.setOffline(profile === 'offline');
+    if (profile === 'offline' || (profile === 'none' && !this.networkThrottled)) {
+      return;
+    }
+    const conditions =
+      profile === 'none'
+        ? {}
+        : profile === 'custom'
+          ? custom
+          : NETWORK_PROFILES[profile];
+    // CDP wants bytes per second; -1 disables throttling
+    const bytesPerSecond = (kbps?: number) => (kbps ? (kbps * 1000) / 8 : -1);
+    const cdp = await this.getCDPSession();
+    await cdp.send('Network.emulateNetworkConditions', {
+      offline: false,
+      latency: conditions.latency ?? 0,
+      downloadThroughput: bytesPerSecond(conditions.downloadKbps),
+      uploadThroughput: bytesPerSecond(conditions.uploadKbps),
+    });
+    this.networkThrottled = profile !== 'none';
+  }
+
+  /**
+   * Slow the CPU down by `rate` (1 = full speed). Chromium only.
+   */
+  async setCpuThrottling(rate: number): Promise<void> {
+    const cdp = await this.getCDPSession();
+    await cdp.send('Emulation.setCPUThrottlingRate', { rate });
+  }
+
   /**
    * Set extra HTTP headers (global - all requests)
    */
@@ -776,7 +1098,7 @@ export class BrowserManager {
       this.browserbaseSessionId = session.id;
       this.browserbaseApiKey = browserbaseApiKey;
       this.browser = browser;
-      context.setDefaultTimeout(10000);
+      this.setDefaultTimeout(context, 10000);
       this.contexts.push(context);
       this.setupContextTracking(context);
       this.pages.push(page);
@@ -917,7 +1239,7 @@ export class BrowserManager {
       this.kernelSessionId = session.session_id;
       this.kernelApiKey = kernelApiKey;
       this.browser = browser;
-      context.setDefaultTimeout(60000);
+      this.setDefaultTimeout(context, 60000);
       this.contexts.push(context);
       this.pages.push(page);
       this.activePageIndex = 0;
@@ -990,7 +1312,7 @@ export class BrowserManager {
       this.browserUseSessionId = session.id;
       this.browserUseApiKey = browserUseApiKey;
       this.browser = browser;
-      context.setDefaultTimeout(60000);
+      this.setDefaultTimeout(context, 60000);
       this.contexts.push(context);
       this.pages.push(page);
       this.activePageIndex = 0;
@@ -1097,6 +1419,8 @@ export class BrowserManager {
           userAgent: options.userAgent,
           ...(options.proxy && { proxy: options.proxy }),
           ignoreHTTPSErrors: options.ignoreHTTPSErrors ?? false,
+          locale: options.locale,
+          httpCredentials: options.httpCredentials,
         }
       );
       this.isPersistentContext = true;
@@ -1109,6 +1433,8 @@ export class BrowserManager {
         executablePath: options.executablePath,
         viewport,
         extraHTTPHeaders: options.headers,
+        locale: options.locale,
+        httpCredentials: options.httpCredentials,
       });
       this.isPersistentContext = true;
     } else {
@@ -1126,10 +1452,12 @@ export class BrowserManager {
         ...(options.proxy && { proxy: options.proxy }),
         ignoreHTTPSErrors: options.ignoreHTTPSErrors ?? false,
         ...(options.storageState && { storageState: options.storageState }),
+        locale: options.locale,
+        httpCredentials: options.httpCredentials,
       });
     }
 
-    context.setDefaultTimeout(60000);
+    this.setDefaultTimeout(context, 60000);
     this.contexts.push(context);
     this.setupContextTracking(context);
 
@@ -1199,7 +1527,7 @@ export class BrowserManager {
       this.cdpEndpoint = cdpEndpoint;
 
       for (const context of contexts) {
-        context.setDefaultTimeout(10000);
+        this.setDefaultTimeout(context, 10000);
         this.contexts.push(context);
         this.setupContextTracking(context);
       }
@@ -1298,7 +1626,7 @@ export class BrowserManager {
     const context = await this.browser.newContext({
       viewport: viewport ?? { width: 1280, height: 720 },
     });
-    context.setDefaultTimeout(60000);
+    this.setDefaultTimeout(context, 60000);
     this.contexts.push(context);
     this.setupContextTracking(context);
 
@@ -1671,7 +1999,7 @@ export class BrowserManager {
       },
       storageState,
     });
-    this.recordingContext.setDefaultTimeout(10000);
+    this.setDefaultTimeout(this.recordingContext, 10000);
 
     // Create a page in the recording context
     this.recordingPage = await this.recordingContext.newPage();
@@ -3,9 +3,10 @@ import * as fs from 'fs';
 import * as path from 'path';
 import * as os from 'os';
 import { BrowserManager } from './browser.js';
-import { parseCommand, serializeResponse, errorResponse } from './protocol.js';
+import { parseCommand, serializeResponse, errorResponse, successResponse } from './protocol.js';
 import { executeCommand } from './actions.js';
 import { StreamServer } from './stream-server.js';
+import type { Response } from './types.js';
 
 // Platform detection
 const isWindows = process.platform === 'win32';
@@ -181,6 +182,9 @@ export async function startDaemon(options?: { streamPort?: number }): Promise<vo
   const browser = new BrowserManager();
   let shuttingDown = false;
 
+  // In-flight requests by id, aborted when a client cancels them
+  const pending = new Map<string, AbortController>();
+
   // Start stream server if port is specified (or use default if env var is set)
   const streamPort =
     options?.streamPort ??
@@ -237,7 +241,8 @@ export async function startDaemon(options?: { streamPort?: number }): Promise<vo
           if (
             !browser.isLaunched() &&
             parseResult.command.action !== 'launch' &&
-            parseResult.command.action !== 'close'
+            parseResult.command.action !== 'close' &&
+            parseResult.command.action !== 'cancel'
           ) {
             const extensions = process.env.AGENT_BROWSER_EXTENSIONS
               ? process.env.AGENT_BROWSER_EXTENSIONS.split(',')
@@ -280,6 +285,20 @@ export async function startDaemon(options?: { streamPort?: number }): Promise<vo
             });
           }
 
+          // Cancel an in-flight request (the CLI was interrupted): answer it
+          // now and stop the action's waits; an action that can't be stopped
+          // midway has its eventual result discarded
+          if (parseResult.command.action === 'cancel') {
+            const { id, target } = parseResult.command;
+            const controller = pending.get(target);
+            controller?.abort(new Error('Cancelled'));
+            socket.write(
+              serializeResponse(successResponse(id, { cancelled: controller !== undefined })) +
+                '\n'
+            );
+            continue;
+          }
+
           // Handle close command specially
           if (parseResult.command.action === 'close') {
             const response = await executeCommand(parseResult.command, browser);
@@ -296,7 +315,15 @@ export async function startDaemon(options?: { streamPort?: number }): Promise<vo
             return;
           }
 
-          const response = await executeCommand(parseResult.command, browser);
+          const command = parseResult.command;
+          const controller = new AbortController();
+          pending.set(command.id, controller);
+          const response = await new Promise<Response>((resolve, reject) => {
+            controller.signal.addEventListener('abort', () =>
+              resolve(errorResponse(command.id, 'Cancelled'))
+            );
+            executeCommand(command, browser, controller.signal).then(resolve, reject);
+          }).finally(() => pending.delete(command.id));
           socket.write(serializeResponse(response) + '\n');
         } catch (err) {
           const message = err instanceof Error ? err.message : String(err);
@@ -0,0 +1,27 @@
+import { describe, it, expect } from 'vitest';
+import { urlMatcher } from './har.js';
+
+describe('urlMatcher', () => {
+  it('should match everything without a filter', () => {
+    expect(urlMatcher()('https://example.com/')).toBe(true);
+  });
+
+  it('should match globs', () => {
+    const matches = urlMatcher('**/api/*');
+    expect(matches('https://example.com/api/users')).toBe(true);
+    expect(matches('https://example.com/api/users/1')).toBe(false);
+    expect(matches('https://example.com/static/app.js')).toBe(false);
+  });
+
+  it('should match regexes', () => {
+    const matches = urlMatcher('/\\.json$/i');
+    expect(matches('https://example.com/data.JSON')).toBe(true);
+    expect(matches('https://example.com/data.js')).toBe(false);
+  });
+
+  it('should match substrings', () => {
+    const matches = urlMatcher('api.example.com');
+    expect(matches('https://api.example.com/v1')).toBe(true);
+    expect(matches('https://example.com/')).toBe(false);
+  });
+});
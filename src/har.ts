@@ -0,0 +1,305 @@
+/**
+ * HAR 1.2 recording of a browser context's network traffic.
+ *
+ * Playwright can only record a HAR for contexts created with `recordHar`, so
+ * sessions that are already running build the log themselves from request
+ * events. Bodies are embedded, omitted or attached as files next to the HAR,
+ * like Playwright's own `content` option.
+ *
+ * Usage:
+ *   agent-browser har start --content attach --url-filter api.example.com
+ *   agent-browser har stop ./bug-1234.har
+ */
+
+import { createHash } from 'node:crypto';
+import { mkdirSync, writeFileSync } from 'node:fs';
+import path from 'node:path';
+import type { BrowserContext, Request, Response } from 'playwright-core';
+
+export type HarContentMode = 'embed' | 'omit' | 'attach';
+
+export interface HarOptions {
+  /** How response bodies are stored (default: embed) */
+  content?: HarContentMode;
+  /** Only record URLs matching this glob, `/regex/` or substring */
+  urlFilter?: string;
+}
+
+interface HarHeader {
+  name: string;
+  value: string;
+}
+
+export interface HarEntry {
+  startedDateTime: string;
+  time: number;
+  request: {
+    method: string;
+    url: string;
+    httpVersion: string;
+    cookies: never[];
+    headers: HarHeader[];
+    queryString: HarHeader[];
+    postData?: { mimeType: string; text: string };
+    headersSize: number;
+    bodySize: number;
+  };
+  response: {
+    status: number;
+    statusText: string;
+    httpVersion: string;
+    cookies: never[];
+    headers: HarHeader[];
+    content: {
+      size: number;
+      mimeType: string;
+      text?: string;
+      encoding?: string;
+      _file?: string;
+    };
+    redirectURL: string;
+    headersSize: number;
+    bodySize: number;
+  };
+  cache: Record<string, never>;
+  timings: {
+    blocked: number;
+    dns: number;
+    connect: number;
+    ssl: number;
+    send: number;
+    wait: number;
+    receive: number;
+  };
+  serverIPAddress?: string;
+  _resourceType: string;
+  _failureText?: string;
+}
+
+/**
+ * Turn a `--url-filter` into a predicate: `/regex/flags`, a glob where `**`
+ * matches anything and `*` anything but `/`, or otherwise a substring
+ */
+export function urlMatcher(filter?: string): (url: string) => boolean {
+  if (!filter) return () => true;
+  const regex = filter.match(/^\/(.+)\/([a-z]*)$/);
+  if (regex) {
+    const re = new RegExp(regex[1], regex[2]);
+    return (url) => re.test(url);
+  }
+  if (filter.includes('*')) {
+    const source = filter
+      .split('**')
+      .map((part) =>
+        part
+          .split('*')
+          .map((s) => s.replace(/[.+?^${}()|[\]\\]/g, '\\$&'))
+          .join('[^/]*')
+      )
+      .join('.*');
+    const re = new RegExp(`^${source}$`);
+    return (url) => re.test(url);
+  }
+  return (url) => url.includes(filter);
+}
+
+/** Whether a body of this MIME type (without parameters) is readable text */
+export function isText(mimeType: string): boolean {
+  return (
+    mimeType.startsWith('text/') ||
+    /json|javascript|xml|html|css|svg|urlencoded/.test(mimeType) ||
+    mimeType === ''
+  );
+}
+
+/** Milliseconds between two timing marks, or -1 if either is missing */
+function span(start: number, end: number): number {
+  return start >= 0 && end >= 0 ? end - start : -1;
+}
+
+function queryString(url: string): HarHeader[] {
+  try {
+    return [...new URL(url).searchParams].map(([name, value]) => ({ name, value }));
+  } catch {
+    return [];
+  }
+}
+
+const EXTENSIONS: Record<string, string> = {
+  'application/json': 'json',
+  'text/html': 'html',
+  'text/css': 'css',
+  'text/plain': 'txt',
+  'application/javascript': 'js',
+  'text/javascript': 'js',
+  'image/png': 'png',
+  'image/jpeg': 'jpg',
+  'image/gif': 'gif',
+  'image/svg+xml': 'svg',
+  'image/webp': 'webp',
+};
+
+export class HarRecorder {
+  private entries: HarEntry[] = [];
+  /** Bodies of attached entries, written out when the HAR is saved */
+  private attachments: Map<HarEntry, Buffer> = new Map();
+  private pending: Set<Promise<void>> = new Set();
+  private readonly content: HarContentMode;
+  private readonly matches: (url: string) => boolean;
+  private readonly listeners: Array<() => void> = [];
+
+  constructor(contexts: BrowserContext[], options: HarOptions = {}) {
+    this.content = options.content ?? 'embed';
+    this.matches = urlMatcher(options.urlFilter);
+    for (const context of contexts) {
+      const onFinished = (request: Request) => this.track(this.record(request));
+      const onFailed = (request: Request) => this.track(this.record(request));
+      context.on('requestfinished', onFinished);
+      context.on('requestfailed', onFailed);
+      this.listeners.push(() => {
+        context.off('requestfinished', onFinished);
+        context.off('requestfailed', onFailed);
+      });
+    }
+  }
+
+  private track(promise: Promise<void>): void {
+    this.pending.add(promise);
+    promise.finally(() => this.pending.delete(promise));
+  }
+
+  private async record(request: Request): Promise<void> {
+    if (!this.matches(request.url())) return;
+    const timing = request.timing();
+    const response = await request.response().catch(() => null);
+    const requestHeaders = await request.headersArray().catch(() => []);
+    const postData = request.postData();
+
+    const entry: HarEntry = {
+      startedDateTime: new Date(timing.startTime || Date.now()).toISOString(),
+      time: 0,
+      request: {
+        method: request.method(),
+        url: request.url(),
+        httpVersion: 'HTTP/1.1',
+        cookies: [],
+        headers: requestHeaders,
+        queryString: queryString(request.url()),
+        headersSize: -1,
+        bodySize: postData ? Buffer.byteLength(postData) : 0,
+      },
+      response: {
+        status: 0,
+        statusText: '',
+        httpVersion: 'HTTP/1.1',
+        cookies: [],
+        headers: [],
+        content: { size: -1, mimeType: 'x-unknown' },
+        redirectURL: '',
+        headersSize: -1,
+        bodySize: -1,
+      },
+      cache: {},
+      timings: {
+        blocked: -1,
+        dns: span(timing.domainLookupStart, timing.domainLookupEnd),
+        connect: span(timing.connectStart, timing.connectEnd),
+        ssl: span(timing.secureConnectionStart, timing.connectEnd),
+        send: 0,
+        wait: span(timing.requestStart, timing.responseStart),
+        receive: span(timing.responseStart, timing.responseEnd),
+      },
+      _resourceType: request.resourceType(),
+    };
+    if (postData) {
+      entry.request.postData = {
+        mimeType: (await request.headerValue('content-type').catch(() => null)) ?? '',
+        text: postData,
+      };
+    }
+    entry.time = Math.max(0, timing.responseEnd >= 0 ? timing.responseEnd : 0);
+
+    const failure = request.failure();
+    if (failure) entry._failureText = failure.errorText;
+    if (response) await this.recordResponse(entry, response);
+    this.entries.push(entry);
+  }
+
+  private async recordResponse(entry: HarEntry, response: Response): Promise<void> {
+    const headers = await response.headersArray().catch(() => []);
+    const mimeType =
+      headers.find((h) => h.name.toLowerCase() === 'content-type')?.value ?? 'x-unknown';
+    entry.response.status = response.status();
+    entry.response.statusText = response.statusText();
+    entry.response.headers = headers;
+    entry.response.content.mimeType = mimeType;
+    entry.response.redirectURL =
+      headers.find((h) => h.name.toLowerCase() === 'location')?.value ?? '';
+    const address = await response.serverAddr().catch(() => null);
+    if (address) entry.serverIPAddress = address.ipAddress;
+
+    // Redirects have no body to read
+    const body =
+      entry.response.redirectURL === '' ? await response.body().catch(() => null) : null;
+    if (!body) {
+      entry.response.content.size = 0;
+      entry.response.bodySize = 0;
+      return;
+    }
+    entry.response.content.size = body.length;
+    entry.response.bodySize = body.length;
+    if (this.content === 'embed') {
+      const mime = mimeType.split(';')[0].trim();
+      if (isText(mime)) {
+        entry.response.content.text = body.toString('utf8');
+      } else {
+        entry.response.content.text = body.toString('base64');
+        entry.response.content.encoding = 'base64';
+      }
+    } else if (this.content === 'attach') {
+      this.attachments.set(entry, body);
+    }
+  }
+
+  /** Number of entries recorded so far */
+  get count(): number {
+    return this.entries.length;
+  }
+
+  /**
+   * Stop listening, wait for in-flight entries and write the HAR (and any
+   * attached bodies, next to it)
+   */
+  async save(
+    harPath: string,
+    browser?: { name: string; version: string }
+  ): Promise<number> {
+    for (const off of this.listeners) off();
+    await Promise.allSettled([...this.pending]);
+
+    const dir = path.dirname(harPath);
+    mkdirSync(dir, { recursive: true });
+    for (const [entry, body] of this.attachments) {
+      const mime = entry.response.content.mimeType.split(';')[0].trim();
+      const hash = createHash('sha1').update(body).digest('hex');
+      const name = `${hash}.${EXTENSIONS[mime] ?? 'dat'}`;
+      writeFileSync(path.join(dir, name), body);
+      entry.response.content._file = name;
+    }
+
+    const entries = [...this.entries].sort((a, b) =>
+      a.startedDateTime.localeCompare(b.startedDateTime)
+    );
+    const har = {
+      log: {
+        version: '1.2',
+        creator: { name: 'agent-browser', version: '' },
+        browser,
+        pages: [],
+        entries,
+      },
+    };
+    writeFileSync(harPath, JSON.stringify(har, null, 2));
+    return entries.length;
+  }
+}
@@ -254,6 +254,14 @@ describe('parseCommand', () => {
         expect(result.command.action).toBe('cookies_clear');
       }
     });
+
+    it('should parse context_reset', () => {
+      const result = parseCommand(cmd({ id: '1', action: 'context_reset' }));
+      expect(result.success).toBe(true);
+      if (result.success) {
+        expect(result.command.action).toBe('context_reset');
+      }
+    });
   });
 
   describe('storage', () => {
@@ -420,6 +428,18 @@ describe('parseCommand', () => {
     });
   });
 
+  describe('cancel', () => {
+    it('should parse cancel with a target id', () => {
+      const result = parseCommand(cmd({ id: '2', action: 'cancel', target: 'r123-1-0' }));
+      expect(result.success).toBe(true);
+    });
+
+    it('should reject cancel without a target', () => {
+      const result = parseCommand(cmd({ id: '2', action: 'cancel' }));
+      expect(result.success).toBe(false);
+    });
+  });
+
   describe('snapshot', () => {
     it('should parse basic snapshot command', () => {
       const result = parseCommand(cmd({ id: '1', action: 'snapshot' }));
@@ -531,6 +551,29 @@ describe('parseCommand', () => {
       const result = parseCommand(cmd({ id: '1', action: 'launch', ignoreHTTPSErrors: 'true' }));
       expect(result.success).toBe(false);
     });
+
+    it('should parse launch with locale and httpCredentials', () => {
+      const result = parseCommand(
+        cmd({
+          id: '1',
+          action: 'launch',
+          locale: 'de-DE',
+          httpCredentials: { username: 'qa', password: 'secret' },
+        })
+      );
+      expect(result.success).toBe(true);
+      if (result.success) {
+        expect(result.command.locale).toBe('de-DE');
+        expect(result.command.httpCredentials?.username).toBe('qa');
+      }
+    });
+
+    it('should reject launch with incomplete httpCredentials', () => {
+      const result = parseCommand(
+        cmd({ id: '1', action: 'launch', httpCredentials: { username: 'qa' } })
+      );
+      expect(result.success).toBe(false);
+    });
   });
 
   describe('mouse actions', () => {
@@ -621,6 +664,209 @@ describe('parseCommand', () => {
     });
   });
 
+  describe('har', () => {
+    it('should parse har_start with options', () => {
+      const result = parseCommand(
+        cmd({ id: '1', action: 'har_start', content: 'attach', urlFilter: '**/api/**' })
+      );
+      expect(result.success).toBe(true);
+      if (result.success && result.command.action === 'har_start') {
+        expect(result.command.content).toBe('attach');
+        expect(result.command.urlFilter).toBe('**/api/**');
+      }
+    });
+
+    it('should reject unknown har content mode', () => {
+      const result = parseCommand(cmd({ id: '1', action: 'har_start', content: 'inline' }));
+      expect(result.success).toBe(false);
+    });
+
+    it('should parse har_replay', () => {
+      const result = parseCommand(
+        cmd({
+          id: '1',
+          action: 'har_replay',
+          path: '/tmp/app.har',
+          notFound: 'fallback',
+          url: '**/api/**',
+        })
+      );
+      expect(result.success).toBe(true);
+      if (result.success && result.command.action === 'har_replay') {
+        expect(result.command.notFound).toBe('fallback');
+        expect(result.command.url).toBe('**/api/**');
+      }
+    });
+
+    it('should reject har_replay without path or with unknown notFound', () => {
+      expect(parseCommand(cmd({ id: '1', action: 'har_replay' })).success).toBe(false);
+      expect(
+        parseCommand(cmd({ id: '1', action: 'har_replay', path: 'a.har', notFound: 'skip' }))
+          .success
+      ).toBe(false);
+    });
+
+    it('should require har_stop path', () => {
+      const result = parseCommand(cmd({ id: '1', action: 'har_stop' }));
+      expect(result.success).toBe(false);
+    });
+  });
+
+  describe('route', () => {
+    it('should parse route with status, headers, delay, times and method', () => {
+      const result = parseCommand(
+        cmd({
+          id: '1',
+          action: 'route',
+          url: '**/api/*',
+          method: 'POST',
+          response: { status: 503, headers: { 'Retry-After': '30' }, body: 'down' },
+          delay: 2000,
+          times: 2,
+        })
+      );
+      expect(result.success).toBe(true);
+      if (result.success && result.command.action === 'route') {
+        expect(result.command.response?.status).toBe(503);
+        expect(result.command.delay).toBe(2000);
+        expect(result.command.times).toBe(2);
+      }
+    });
+
+    it('should reject invalid status, delay and times', () => {
+      const route = { id: '1', action: 'route', url: '**' };
+      expect(parseCommand(cmd({ ...route, response: { status: 42 } })).success).toBe(false);
+      expect(parseCommand(cmd({ ...route, delay: -1 })).success).toBe(false);
+      expect(parseCommand(cmd({ ...route, times: 0 })).success).toBe(false);
+    });
+
+    it('should parse requests filters', () => {
+      const result = parseCommand(
+        cmd({
+          id: '1',
+          action: 'requests',
+          method: 'POST',
+          resourceType: 'fetch',
+          status: '4xx,500-599',
+          since: 1700000000000,
+          failed: true,
+        })
+      );
+      expect(result.success).toBe(true);
+    });
+
+    it('should reject invalid requests status patterns', () => {
+      expect(parseCommand(cmd({ id: '1', action: 'requests', status: '4x' })).success).toBe(
+        false
+      );
+    });
+
+    it('should parse network_body by index or url', () => {
+      expect(parseCommand(cmd({ id: '1', action: 'network_body', index: 3 })).success).toBe(true);
+      expect(
+        parseCommand(
+          cmd({ id: '1', action: 'network_body', url: '**/api/*', path: '/tmp/body.json' })
+        ).success
+      ).toBe(true);
+      expect(parseCommand(cmd({ id: '1', action: 'network_body', index: 0 })).success).toBe(false);
+    });
+
+    it('should parse responsebody with a path', () => {
+      const result = parseCommand(
+        cmd({ id: '1', action: 'responsebody', url: '/api/', timeout: 5000, path: '/tmp/a.json' })
+      );
+      expect(result.success).toBe(true);
+    });
+
+    it('should parse block and unblock', () => {
+      const result = parseCommand(
+        cmd({ id: '1', action: 'block', types: ['image', 'font'], domains: ['@ads'] })
+      );
+      expect(result.success).toBe(true);
+      expect(parseCommand(cmd({ id: '1', action: 'block' })).success).toBe(true);
+      expect(parseCommand(cmd({ id: '1', action: 'block', types: [''] })).success).toBe(false);
+      expect(parseCommand(cmd({ id: '1', action: 'unblock' })).success).toBe(true);
+    });
+
+    it('should parse mock rules', () => {
+      const result = parseCommand(
+        cmd({
+          id: '1',
+          action: 'mock',
+          rules: [
+            { url: '**/api/users', response: { status: 500 } },
+            { url: '**/ads/**', abort: true },
+            { url: '**/avatar.png', response: { path: '/tmp/avatar.png' }, times: 1 },
+          ],
+        })
+      );
+      expect(result.success).toBe(true);
+      if (result.success && result.command.action === 'mock') {
+        expect(result.command.rules).toHaveLength(3);
+      }
+    });
+
+    it('should reject mock without rules', () => {
+      expect(parseCommand(cmd({ id: '1', action: 'mock', rules: [] })).success).toBe(false);
+      expect(parseCommand(cmd({ id: '1', action: 'mock', rules: [{}] })).success).toBe(false);
+    });
+  });
+
+  describe('fetch', () => {
+    it('should parse fetch', () => {
+      const result = parseCommand(
+        cmd({
+          id: '1',
+          action: 'fetch',
+          url: '/api/orders',
+          method: 'POST',
+          headers: { 'Content-Type': 'application/json' },
+          data: '{"qty":2}',
+          path: '/tmp/order.json',
+        })
+      );
+      expect(result.success).toBe(true);
+      if (result.success && result.command.action === 'fetch') {
+        expect(result.command.method).toBe('POST');
+        expect(result.command.data).toBe('{"qty":2}');
+      }
+    });
+
+    it('should require fetch url', () => {
+      expect(parseCommand(cmd({ id: '1', action: 'fetch' })).success).toBe(false);
+    });
+  });
+
+  describe('throttling', () => {
+    it('should parse network_conditions', () => {
+      expect(
+        parseCommand(cmd({ id: '1', action: 'network_conditions', profile: 'slow-3g' })).success
+      ).toBe(true);
+      expect(
+        parseCommand(
+          cmd({
+            id: '1',
+            action: 'network_conditions',
+            profile: 'custom',
+            downloadKbps: 1000,
+            uploadKbps: 500,
+            latency: 300,
+          })
+        ).success
+      ).toBe(true);
+      expect(
+        parseCommand(cmd({ id: '1', action: 'network_conditions', profile: '2g' })).success
+      ).toBe(false);
+    });
+
+    it('should parse cpu_throttle with a rate of at least 1', () => {
+      expect(parseCommand(cmd({ id: '1', action: 'cpu_throttle', rate: 4 })).success).toBe(true);
+      expect(parseCommand(cmd({ id: '1', action: 'cpu_throttle', rate: 0.5 })).success).toBe(
+        false
+      );
+    });
+  });
+
   describe('console and errors', () => {
     it('should parse console', () => {
       const result = parseCommand(cmd({ id: '1', action: 'console' }));
@@ -48,6 +48,13 @@ const launchSchema = baseCommandSchema.extend({
   ignoreHTTPSErrors: z.boolean().optional(),
   profile: z.string().optional(),
   storageState: z.string().optional(),
+  locale: z.string().optional(),
+  httpCredentials: z
+    .object({
+      username: z.string(),
+      password: z.string(),
+    })
+    .optional(),
 });
 
 const navigateSchema = baseCommandSchema.extend({
@@ -177,6 +184,10 @@ const cookiesClearSchema = baseCommandSchema.extend({
   action: z.literal('cookies_clear'),
 });
 
+const contextResetSchema = baseCommandSchema.extend({
+  action: z.literal('context_reset'),
+});
+
 const storageGetSchema = baseCommandSchema.extend({
   action: z.literal('storage_get'),
   key: z.string().optional(),
@@ -209,18 +220,41 @@ const pdfSchema = baseCommandSchema.extend({
     .optional(),
 });
 
-const routeSchema = baseCommandSchema.extend({
-  action: z.literal('route'),
+const routeRuleSchema = z.object({
   url: z.string().min(1),
+  method: z.string().optional(),
   response: z
     .object({
-      status: z.number().optional(),
+      status: z.number().int().min(100).max(599).optional(),
       body: z.string().optional(),
+      path: z.string().optional(),
       contentType: z.string().optional(),
       headers: z.record(z.string()).optional(),
     })
     .optional(),
   abort: z.boolean().optional(),
+  delay: z.number().int().nonnegative().optional(),
+  times: z.number().int().positive().optional(),
+});
+
+const routeSchema = baseCommandSchema.extend({
+  action: z.literal('route'),
+  ...routeRuleSchema.shape,
+});
+
+const mockSchema = baseCommandSchema.extend({
+  action: z.literal('mock'),
+  rules: z.array(routeRuleSchema).min(1),
+});
+
+const blockSchema = baseCommandSchema.extend({
+  action: z.literal('block'),
+  types: z.array(z.string().min(1)).optional(),
+  domains: z.array(z.string().min(1)).optional(),
+});
+
+const unblockSchema = baseCommandSchema.extend({
+  action: z.literal('unblock'),
 });
 
 const unrouteSchema = baseCommandSchema.extend({
@@ -232,6 +266,14 @@ const requestsSchema = baseCommandSchema.extend({
   action: z.literal('requests'),
   filter: z.string().optional(),
   clear: z.boolean().optional(),
+  method: z.string().optional(),
+  resourceType: z.string().optional(),
+  status: z
+    .string()
+    .regex(/^\s*([1-5]xx|\d{3}(-\d{3})?)\s*(,\s*([1-5]xx|\d{3}(-\d{3})?)\s*)*$/i)
+    .optional(),
+  since: z.number().nonnegative().optional(),
+  failed: z.boolean().optional(),
 });
 
 const downloadSchema = baseCommandSchema.extend({
@@ -369,6 +411,8 @@ const traceStopSchema = baseCommandSchema.extend({
 
 const harStartSchema = baseCommandSchema.extend({
   action: z.literal('har_start'),
+  content: z.enum(['embed', 'omit', 'attach']).optional(),
+  urlFilter: z.string().min(1).optional(),
 });
 
 const harStopSchema = baseCommandSchema.extend({
@@ -376,6 +420,13 @@ const harStopSchema = baseCommandSchema.extend({
   path: z.string().min(1),
 });
 
+const harReplaySchema = baseCommandSchema.extend({
+  action: z.literal('har_replay'),
+  path: z.string().min(1),
+  notFound: z.enum(['abort', 'fallback']).optional(),
+  url: z.string().min(1).optional(),
+});
+
 const stateSaveSchema = baseCommandSchema.extend({
   action: z.literal('state_save'),
   path: z.string().min(1),
@@ -497,6 +548,19 @@ const offlineSchema = baseCommandSchema.extend({
   offline: z.boolean(),
 });
 
+const networkConditionsSchema = baseCommandSchema.extend({
+  action: z.literal('network_conditions'),
+  profile: z.enum(['none', 'offline', 'slow-3g', 'fast-3g', 'custom']),
+  downloadKbps: z.number().positive().optional(),
+  uploadKbps: z.number().positive().optional(),
+  latency: z.number().nonnegative().optional(),
+});
+
+const cpuThrottleSchema = baseCommandSchema.extend({
+  action: z.literal('cpu_throttle'),
+  rate: z.number().min(1),
+});
+
 const headersSchema = baseCommandSchema.extend({
   action: z.literal('headers'),
   headers: z.record(z.string()),
@@ -635,6 +699,26 @@ const responseBodySchema = baseCommandSchema.extend({
   action: z.literal('responsebody'),
   url: z.string().min(1),
   timeout: z.number().positive().optional(),
+  path: z.string().min(1).optional(),
+});
+
+// Body of a response already in the request log, by log index or URL
+const networkBodySchema = baseCommandSchema.extend({
+  action: z.literal('network_body'),
+  url: z.string().min(1).optional(),
+  index: z.number().int().positive().optional(),
+  path: z.string().min(1).optional(),
+});
+
+const fetchSchema = baseCommandSchema.extend({
+  action: z.literal('fetch'),
+  url: z.string().min(1),
+  method: z.string().min(1).optional(),
+  headers: z.record(z.string()).optional(),
+  data: z.string().optional(),
+  dataFile: z.string().min(1).optional(),
+  path: z.string().min(1).optional(),
+  timeout: z.number().positive().optional(),
 });
 
 // Screencast schemas for streaming browser viewport
@@ -751,6 +835,11 @@ const closeSchema = baseCommandSchema.extend({
   action: z.literal('close'),
 });
 
+const cancelSchema = baseCommandSchema.extend({
+  action: z.literal('cancel'),
+  target: z.string().min(1),
+});
+
 // Tab/Window schemas
 const tabNewSchema = baseCommandSchema.extend({
   action: z.literal('tab_new'),
@@ -810,6 +899,7 @@ const commandSchema = z.discriminatedUnion('action', [
   hoverSchema,
   contentSchema,
   closeSchema,
+  cancelSchema,
   tabNewSchema,
   tabListSchema,
   tabSwitchSchema,
@@ -818,13 +908,17 @@ const commandSchema = z.discriminatedUnion('action', [
   cookiesGetSchema,
   cookiesSetSchema,
   cookiesClearSchema,
+  contextResetSchema,
   storageGetSchema,
   storageSetSchema,
   storageClearSchema,
   dialogSchema,
   pdfSchema,
   routeSchema,
+  mockSchema,
   unrouteSchema,
+  blockSchema,
+  unblockSchema,
   requestsSchema,
   downloadSchema,
   geolocationSchema,
@@ -854,6 +948,7 @@ const commandSchema = z.discriminatedUnion('action', [
   traceStopSchema,
   harStartSchema,
   harStopSchema,
+  harReplaySchema,
   stateSaveSchema,
   stateLoadSchema,
   consoleSchema,
@@ -876,6 +971,8 @@ const commandSchema = z.discriminatedUnion('action', [
   addStyleSchema,
   emulateMediaSchema,
   offlineSchema,
+  networkConditionsSchema,
+  cpuThrottleSchema,
   headersSchema,
   pauseSchema,
   getByAltTextSchema,
@@ -901,6 +998,8 @@ const commandSchema = z.discriminatedUnion('action', [
   multiSelectSchema,
   waitForDownloadSchema,
   responseBodySchema,
+  networkBodySchema,
+  fetchSchema,
   screencastStartSchema,
   screencastStopSchema,
   inputMouseSchema,
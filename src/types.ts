@@ -29,6 +29,11 @@ export interface LaunchCommand extends BaseCommand {
   userAgent?: string;
   provider?: string;
   ignoreHTTPSErrors?: boolean;
+  locale?: string;
+  httpCredentials?: {
+    username: string;
+    password: string;
+  };
 }
 
 export interface NavigateCommand extends BaseCommand {
@@ -156,6 +161,11 @@ export interface CookiesClearCommand extends BaseCommand {
   action: 'cookies_clear';
 }
 
+/** Close the browser; the next command launches a fresh one */
+export interface ContextResetCommand extends BaseCommand {
+  action: 'context_reset';
+}
+
 export interface StorageGetCommand extends BaseCommand {
   action: 'storage_get';
   key?: string;
@@ -198,16 +208,39 @@ export interface PdfCommand extends BaseCommand {
 }
 
 // Network interception
-export interface RouteCommand extends BaseCommand {
-  action: 'route';
+export interface RouteRule {
   url: string;
+  method?: string; // Only match this HTTP method
   response?: {
     status?: number;
     body?: string;
+    path?: string; // Respond with this file instead of a body
     contentType?: string;
     headers?: Record<string, string>;
   };
   abort?: boolean;
+  delay?: number; // Milliseconds to wait before answering
+  times?: number; // Only handle this many requests
+}
+
+export interface RouteCommand extends BaseCommand, RouteRule {
+  action: 'route';
+}
+
+export interface MockCommand extends BaseCommand {
+  action: 'mock';
+  rules: RouteRule[]; // First match wins
+}
+
+// Abort requests by resource type or domain
+export interface BlockCommand extends BaseCommand {
+  action: 'block';
+  types?: string[]; // image, font, media, stylesheet, ...
+  domains?: string[]; // Domains (and their subdomains), or @ads for the bundled list
+}
+
+export interface UnblockCommand extends BaseCommand {
+  action: 'unblock';
 }
 
 export interface UnrouteCommand extends BaseCommand {
@@ -220,6 +253,11 @@ export interface RequestsCommand extends BaseCommand {
   action: 'requests';
   filter?: string; // URL pattern to filter
   clear?: boolean;
+  method?: string;
+  resourceType?: string; // document, xhr, fetch, script, image, ...
+  status?: string; // 404, 4xx, 500-599 or a comma-separated list
+  since?: number; // Only requests started since (ms since epoch)
+  failed?: boolean; // Only requests that got no response
 }
 
 // Download handling
@@ -474,7 +512,28 @@ export interface WaitForDownloadCommand extends BaseCommand {
 // Get response body from intercepted request
 export interface ResponseBodyCommand extends BaseCommand {
   action: 'responsebody';
-  url: string;
+  url: string; // Glob, /regex/ or substring
+  timeout?: number;
+  path?: string; // Save the body here instead of returning it
+}
+
+// Get the body of a response already in the request log
+export interface NetworkBodyCommand extends BaseCommand {
+  action: 'network_body';
+  url?: string; // Most recent response matching this glob, /regex/ or substring
+  index?: number; // Or the request at this log index
+  path?: string; // Save the body here instead of returning it
+}
+
+// HTTP request with the browser context's cookies
+export interface FetchCommand extends BaseCommand {
+  action: 'fetch';
+  url: string; // Absolute, or a path relative to the current page
+  method?: string; // Default: POST with data, else GET
+  headers?: Record<string, string>;
+  data?: string;
+  dataFile?: string; // Send this file's contents as the body
+  path?: string; // Save the response body here instead of returning it
   timeout?: number;
 }
 
@@ -563,6 +622,10 @@ export interface TraceStopCommand extends BaseCommand {
 // HAR recording
 export interface HarStartCommand extends BaseCommand {
   action: 'har_start';
+  /** How response bodies are stored (default: embed) */
+  content?: 'embed' | 'omit' | 'attach';
+  /** Only record URLs matching this glob, `/regex/` or substring */
+  urlFilter?: string;
 }
 
 export interface HarStopCommand extends BaseCommand {
@@ -570,6 +633,15 @@ export interface HarStopCommand extends BaseCommand {
   path: string;
 }
 
+export interface HarReplayCommand extends BaseCommand {
+  action: 'har_replay';
+  path: string;
+  /** What to do with requests the HAR has no entry for (default: abort) */
+  notFound?: 'abort' | 'fallback';
+  /** Only serve URLs matching this glob from the HAR */
+  url?: string;
+}
+
 // Storage state (auth persistence)
 export interface StorageStateSaveCommand extends BaseCommand {
   action: 'state_save';
@@ -710,6 +782,23 @@ export interface OfflineCommand extends BaseCommand {
   offline: boolean;
 }
 
+// Throttle the network (Chromium only, except offline)
+export type NetworkProfile = 'none' | 'offline' | 'slow-3g' | 'fast-3g' | 'custom';
+
+export interface NetworkConditionsCommand extends BaseCommand {
+  action: 'network_conditions';
+  profile: NetworkProfile;
+  downloadKbps?: number; // custom: unlimited if omitted
+  uploadKbps?: number; // custom: unlimited if omitted
+  latency?: number; // custom: added round-trip ms
+}
+
+// Slow down the CPU by a factor (Chromium only)
+export interface CpuThrottleCommand extends BaseCommand {
+  action: 'cpu_throttle';
+  rate: number; // 1 = no throttling
+}
+
 // Set extra HTTP headers
 export interface HeadersCommand extends BaseCommand {
   action: 'headers';
@@ -782,6 +871,12 @@ export interface CloseCommand extends BaseCommand {
   action: 'close';
 }
 
+// Abandon an in-flight request (sent by an interrupted CLI)
+export interface CancelCommand extends BaseCommand {
+  action: 'cancel';
+  target: string;
+}
+
 // Tab/Window commands
 export interface TabNewCommand extends BaseCommand {
   action: 'tab_new';
@@ -836,6 +931,7 @@ export type Command =
   | HoverCommand
   | ContentCommand
   | CloseCommand
+  | CancelCommand
   | TabNewCommand
   | TabListCommand
   | TabSwitchCommand
@@ -844,13 +940,17 @@ export type Command =
   | CookiesGetCommand
   | CookiesSetCommand
   | CookiesClearCommand
+  | ContextResetCommand
   | StorageGetCommand
   | StorageSetCommand
   | StorageClearCommand
   | DialogCommand
   | PdfCommand
   | RouteCommand
+  | MockCommand
   | UnrouteCommand
+  | BlockCommand
+  | UnblockCommand
   | RequestsCommand
   | DownloadCommand
   | GeolocationCommand
@@ -880,6 +980,7 @@ export type Command =
   | TraceStopCommand
   | HarStartCommand
   | HarStopCommand
+  | HarReplayCommand
   | StorageStateSaveCommand
   | StorageStateLoadCommand
   | ConsoleCommand
@@ -902,6 +1003,8 @@ export type Command =
   | AddStyleCommand
   | EmulateMediaCommand
   | OfflineCommand
+  | NetworkConditionsCommand
+  | CpuThrottleCommand
   | HeadersCommand
   | PauseCommand
   | GetByAltTextCommand
@@ -927,6 +1030,8 @@ export type Command =
   | MultiSelectCommand
   | WaitForDownloadCommand
   | ResponseBodyCommand
+  | NetworkBodyCommand
+  | FetchCommand
   | ScreencastStartCommand
   | ScreencastStopCommand
   | InputMouseCommand